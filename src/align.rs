@@ -0,0 +1,168 @@
+use serde::Serialize;
+
+use crate::poem::{Line, Poem};
+
+const SENTENCE_ENDS: [char; 5] = ['。', '！', '？', '；', '…'];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pair {
+    pub original: String,
+    pub translation: String,
+}
+
+/// 将译文切分为句子, 保留句末标点, 并去掉 "译文" 之类的标题行
+pub fn split_sentences(translation: &str) -> Vec<String> {
+    let mut sentences = vec![];
+    for raw in translation.lines() {
+        let line = raw.trim();
+        if line.is_empty() || ["译文", "译文：", "翻译", "翻译："].contains(&line) {
+            continue;
+        }
+        let mut current = String::new();
+        for c in line.chars() {
+            current.push(c);
+            if SENTENCE_ENDS.contains(&c) {
+                push_sentence(&mut sentences, &mut current);
+            }
+        }
+        push_sentence(&mut sentences, &mut current);
+    }
+    sentences
+}
+
+fn push_sentence(sentences: &mut Vec<String>, current: &mut String) {
+    let sentence = current.trim();
+    if sentence.is_empty() {
+        return;
+    }
+    // "……" 之类的连续标点归入上一句
+    if sentence
+        .chars()
+        .all(|c| SENTENCE_ENDS.contains(&c) || c == '”')
+    {
+        if let Some(last) = sentences.last_mut() {
+            last.push_str(sentence);
+            current.clear();
+            return;
+        }
+    }
+    sentences.push(sentence.to_string());
+    current.clear();
+}
+
+/// 按联对齐原文与译文.
+///
+/// 句数相同时逐句对应, 否则按字数比例分配译文; 若某一联分不到译文则认为对齐失败, 返回 None
+pub fn align(poem: &Poem, translation: &str) -> Option<Vec<Pair>> {
+    let originals: Vec<String> = poem
+        .couplets()
+        .iter()
+        .map(|couplet| couplet.iter().map(Line::with_punct).collect())
+        .collect();
+    let sentences = split_sentences(translation);
+    if originals.is_empty() || sentences.len() < originals.len() {
+        return None;
+    }
+    if sentences.len() == originals.len() {
+        return Some(
+            originals
+                .into_iter()
+                .zip(sentences)
+                .map(|(original, translation)| Pair {
+                    original,
+                    translation,
+                })
+                .collect(),
+        );
+    }
+
+    let bounds = cumulative(originals.iter().map(|o| o.chars().count()));
+    let mut pairs: Vec<Pair> = originals
+        .into_iter()
+        .map(|original| Pair {
+            original,
+            translation: String::new(),
+        })
+        .collect();
+    let lengths = cumulative(sentences.iter().map(|s| s.chars().count()));
+    let mut prev = 0.0;
+    for (sentence, end) in sentences.into_iter().zip(lengths) {
+        let middle = (prev + end) / 2.0;
+        prev = end;
+        let idx = bounds
+            .iter()
+            .position(|&b| middle <= b)
+            .unwrap_or(bounds.len() - 1);
+        pairs[idx].translation.push_str(&sentence);
+    }
+    if pairs.iter().any(|p| p.translation.is_empty()) {
+        None
+    } else {
+        Some(pairs)
+    }
+}
+
+/// 累计长度占比, 最后一项为 1.0
+fn cumulative<I: Iterator<Item = usize>>(lengths: I) -> Vec<f32> {
+    let lengths: Vec<usize> = lengths.collect();
+    let total = lengths.iter().sum::<usize>().max(1) as f32;
+    let mut acc = 0;
+    lengths
+        .into_iter()
+        .map(|l| {
+            acc += l;
+            acc as f32 / total
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::JINGYESI;
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            split_sentences(
+                "译文\n明亮的月光洒在窗户纸上，好像地上泛起了一层霜。我禁不住抬起头来……\n"
+            ),
+            vec![
+                "明亮的月光洒在窗户纸上，好像地上泛起了一层霜。",
+                "我禁不住抬起头来……"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_one_to_one() {
+        let poem = Poem::parse("静夜思", JINGYESI);
+        let pairs = align(
+            &poem,
+            "明亮的月光洒在窗户纸上，好像地上泛起了一层霜。\n我禁不住抬起头来，看那天窗外空中的一轮明月，不由得低头沉思，想起远方的家乡。",
+        )
+        .unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].original, "床前明月光，疑是地上霜。");
+        assert!(pairs[1].translation.starts_with("我禁不住"));
+    }
+
+    #[test]
+    fn test_align_by_length() {
+        let poem = Poem::parse("静夜思", JINGYESI);
+        let pairs = align(
+            &poem,
+            "明亮的月光洒在窗户纸上。好像地上泛起了一层霜。我禁不住抬起头来，看那天窗外空中的一轮明月。不由得低头沉思，想起远方的家乡。",
+        )
+        .unwrap();
+        assert!(pairs[0].translation.ends_with("一层霜。"));
+        assert!(pairs[1].translation.starts_with("我禁不住"));
+    }
+
+    #[test]
+    fn test_align_fallback() {
+        let poem = Poem::parse("静夜思", JINGYESI);
+        assert!(align(&poem, "月光如霜。").is_none());
+        assert!(align(&poem, "").is_none());
+    }
+}
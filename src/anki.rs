@@ -0,0 +1,178 @@
+use crate::api::{Article, Sentence};
+use crate::poem::Line;
+
+/// Anki 可直接导入的文本文件扩展名
+pub const EXTENSION: &str = "txt";
+
+/// 卡片模板
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Template {
+    /// 上句问下句
+    NextLine,
+    /// 标题问首句
+    TitleFirstLine,
+    /// 名句问出处
+    SentenceSource,
+}
+
+impl Template {
+    pub const ALL: [Template; 3] = [
+        Template::NextLine,
+        Template::TitleFirstLine,
+        Template::SentenceSource,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Template::NextLine => "上句接下句",
+            Template::TitleFirstLine => "标题接首句",
+            Template::SentenceSource => "名句问出处",
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Template::NextLine => "下句",
+            Template::TitleFirstLine => "首句",
+            Template::SentenceSource => "出处",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+}
+
+/// Anki 标签不能含空白
+fn tag(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// 按模板由文章的诗句结构与名句生成卡片
+pub fn cards(articles: &[Article], sentences: &[Sentence], templates: &[Template]) -> Vec<Card> {
+    let mut cards = vec![];
+    for template in templates {
+        match template {
+            Template::NextLine => {
+                for art in articles {
+                    let lines: Vec<Line> = art.poem().lines().cloned().collect();
+                    for pair in lines.windows(2) {
+                        cards.push(Card {
+                            front: format!("{}\n（{}）", pair[0].with_punct(), art.source()),
+                            back: pair[1].with_punct(),
+                            tags: vec![template.tag().to_string(), tag(&art.writer)],
+                        });
+                    }
+                }
+            }
+            Template::TitleFirstLine => {
+                for art in articles {
+                    if let Some(first) = art.poem().lines().next() {
+                        cards.push(Card {
+                            front: art.source(),
+                            back: first.with_punct(),
+                            tags: vec![template.tag().to_string(), tag(&art.writer)],
+                        });
+                    }
+                }
+            }
+            Template::SentenceSource => {
+                for s in sentences {
+                    cards.push(Card {
+                        front: s.name.trim().to_string(),
+                        back: s.from.trim().to_string(),
+                        tags: vec![template.tag().to_string()],
+                    });
+                }
+            }
+        }
+    }
+    cards
+}
+
+/// 名句中出处提到所选文章标题的那些
+pub fn related_sentences<'a>(articles: &[Article], sentences: &'a [Sentence]) -> Vec<&'a Sentence> {
+    sentences
+        .iter()
+        .filter(|s| {
+            articles
+                .iter()
+                .any(|art| !art.title.is_empty() && s.from.contains(&art.title))
+        })
+        .collect()
+}
+
+/// 含制表符, 换行或引号的字段加引号, 内部引号成对转义
+fn field(text: &str) -> String {
+    if text.contains(&['\t', '\n', '"'][..]) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// 生成可直接导入 Anki 的制表符分隔文件, 文件头指定笔记类型, 牌组与标签列
+pub fn to_tsv(cards: &[Card], deck: &str) -> String {
+    let mut out = format!(
+        "#separator:tab\n#html:false\n#notetype:Basic\n#deck:{}\n#tags column:3\n",
+        deck
+    );
+    for card in cards {
+        out.push_str(&format!(
+            "{}\t{}\t{}\n",
+            field(&card.front),
+            field(&card.back),
+            card.tags.join(" ")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::jingyesi;
+
+    #[test]
+    fn test_cards() {
+        let arts = vec![jingyesi()];
+        let sentences = vec![
+            Sentence {
+                id: "1".to_string(),
+                name: "举头望明月，低头思故乡。".to_string(),
+                from: "李白《静夜思》".to_string(),
+            },
+            Sentence {
+                id: "2".to_string(),
+                name: "春眠不觉晓，处处闻啼鸟。".to_string(),
+                from: "孟浩然《春晓》".to_string(),
+            },
+        ];
+        let related = related_sentences(&arts, &sentences);
+        assert_eq!(related.len(), 1);
+        let related: Vec<Sentence> = related.into_iter().cloned().collect();
+
+        let cards = cards(&arts, &related, &Template::ALL);
+        assert_eq!(cards.len(), 3 + 1 + 1);
+        assert_eq!(cards[0].front, "床前明月光，\n（〔唐代〕李白《静夜思》）");
+        assert_eq!(cards[0].back, "疑是地上霜。");
+        assert_eq!(cards[2].back, "低头思故乡。");
+        assert_eq!(cards[3].front, "〔唐代〕李白《静夜思》");
+        assert_eq!(cards[3].back, "床前明月光，");
+        assert_eq!(cards[4].back, "李白《静夜思》");
+        assert_eq!(cards[4].tags, vec!["出处"]);
+    }
+
+    #[test]
+    fn test_to_tsv() {
+        let cards = cards(&[jingyesi()], &[], &[Template::TitleFirstLine]);
+        let tsv = to_tsv(&cards, "古诗");
+        assert!(tsv.starts_with("#separator:tab\n#html:false\n"));
+        assert!(tsv.contains("#deck:古诗\n"));
+        assert!(tsv.ends_with("〔唐代〕李白《静夜思》\t床前明月光，\t首句 李白\n"));
+        assert_eq!(field("上\n\"下\""), "\"上\n\"\"下\"\"\"");
+    }
+}
@@ -1,9 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 const GUWEN_URL: &str = "https://www.caoxingyu.club/guwen";
 const SENTENCE_URL: &str = "https://www.caoxingyu.club/guwen/sentence";
 const WRITER_URL: &str = "https://www.caoxingyu.club/guwen/writer";
+/// 本地检索结果的每页篇数
+pub const LOCAL_PAGE_SIZE: usize = 10;
 
 #[derive(Error, Debug)]
 pub enum APIError {
@@ -11,6 +13,8 @@ pub enum APIError {
     NotFound,
     #[error("invalid page should be >= 1")]
     InvalidPage,
+    #[error("local search can only be paged in corpus")]
+    LocalOnly,
 }
 
 #[derive(Debug, Clone)]
@@ -19,6 +23,64 @@ pub enum Method {
     Writer(String),
     Dynasty(String),
     Keyword(String),
+    /// 按平水韵韵部在本地语料中检索
+    Rhyme(String),
+    /// 按平仄格式在本地语料中检索
+    Tone(String),
+    /// 按通配符或正则表达式在本地语料中逐句检索
+    Line(String),
+    /// 在本地语料的正文, 翻译, 注释与赏析中全文检索
+    FullText(String),
+    /// 按顺序浏览选集
+    Collection(String),
+    /// 在个人笔记中检索
+    Note(String),
+    /// 按用户标签浏览
+    Tag(String),
+    /// 按自动判定的题材浏览, 可列出多个题材
+    Theme(String),
+    /// 与指定 id 的文章相关的作品
+    Related(String),
+}
+
+impl Method {
+    /// 检索方式及条件, 如 "作者 - 李白"
+    pub fn label(&self) -> String {
+        match self {
+            Method::Page => "总览".to_string(),
+            Method::Dynasty(dynasty) => format!("朝代 - {}", dynasty),
+            Method::Writer(writer) => format!("作者 - {}", writer),
+            Method::Keyword(keyword) => format!("关键字 - {}", keyword),
+            Method::Rhyme(group) => format!("韵部 - {}", group),
+            Method::Tone(pattern) => format!("平仄 - {}", pattern),
+            Method::Line(pattern) => format!("诗句 - {}", pattern),
+            Method::FullText(query) => format!("全文 - {}", query),
+            Method::Collection(name) => format!("选集 - {}", name),
+            Method::Note(query) => format!("笔记 - {}", query),
+            Method::Tag(tag) => format!("标签 - {}", tag),
+            Method::Theme(query) => format!("题材 - {}", query),
+            Method::Related(id) => format!("相关作品 - {}", id),
+        }
+    }
+
+    /// 换用输入的检索条件, 选集与相关作品的条件不来自输入, 保持不变
+    pub fn with_query(&self, query: &str) -> Method {
+        let query = query.trim().to_string();
+        match self {
+            Method::Page => Method::Page,
+            Method::Dynasty(_) => Method::Dynasty(query),
+            Method::Writer(_) => Method::Writer(query),
+            Method::Keyword(_) => Method::Keyword(query),
+            Method::Rhyme(_) => Method::Rhyme(query),
+            Method::Tone(_) => Method::Tone(query),
+            Method::Line(_) => Method::Line(query),
+            Method::FullText(_) => Method::FullText(query),
+            Method::Note(_) => Method::Note(query),
+            Method::Tag(_) => Method::Tag(query),
+            Method::Theme(_) => Method::Theme(query),
+            Method::Collection(_) | Method::Related(_) => self.clone(),
+        }
+    }
 }
 
 impl Default for Method {
@@ -27,10 +89,13 @@ impl Default for Method {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ArticleSimple {
     pub id: String,
     pub title: String,
+    /// 本地检索时匹配到的上下文
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 impl ArticleSimple {
@@ -40,21 +105,56 @@ impl ArticleSimple {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Article {
     pub id: String,
     pub title: String,
     pub writer: String,
+    #[serde(default)]
+    pub dynasty: String,
     // pub r#type: Vec<String>,
     pub content: String,
     pub remark: Option<String>,
     pub translation: Option<String>,
     pub shangxi: Option<String>,
-    #[serde(rename(deserialize = "audioUrl"))]
+    #[serde(rename = "audioUrl")]
     pub audio_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Article {
+    pub fn to_simple(&self) -> ArticleSimple {
+        ArticleSimple {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            snippet: None,
+        }
+    }
+
+    /// 署名, 如 "〔唐代〕李白", 没有朝代时只有作者
+    pub fn byline(&self) -> String {
+        byline(&self.dynasty, &self.writer)
+    }
+
+    /// 出处, 如 "〔唐代〕李白《静夜思》"
+    pub fn source(&self) -> String {
+        source(&self.dynasty, &self.writer, &self.title)
+    }
+}
+
+fn byline(dynasty: &str, writer: &str) -> String {
+    if dynasty.is_empty() {
+        writer.to_string()
+    } else {
+        format!("〔{}〕{}", dynasty, writer)
+    }
+}
+
+/// 由朝代, 作者与标题组成的出处, 用于只有这几项的摘要
+pub fn source(dynasty: &str, writer: &str, title: &str) -> String {
+    format!("{}《{}》", byline(dynasty, writer), title)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ArtListResp {
     pub total: usize,
     pub pages: usize,
@@ -108,33 +208,43 @@ impl ArtListResp {
         Ok(resp)
     }
 
+    /// 将本地检索结果按页切分, 页码从 1 开始
+    pub fn paginate(found: Vec<ArticleSimple>, page: usize, method: Method) -> ArtListResp {
+        let total = found.len();
+        let pages = total.div_ceil(LOCAL_PAGE_SIZE);
+        let data = found
+            .into_iter()
+            .skip((page.max(1) - 1) * LOCAL_PAGE_SIZE)
+            .take(LOCAL_PAGE_SIZE)
+            .collect();
+        ArtListResp {
+            total,
+            pages,
+            page,
+            page_size: LOCAL_PAGE_SIZE,
+            method,
+            data,
+        }
+    }
+
     pub fn show(art_id: &str) -> Result<Article, Box<dyn std::error::Error>> {
         let url = format!("{}/selectbyid?id={}", GUWEN_URL, art_id);
         Ok(reqwest::blocking::get(&url)?.json::<Article>()?)
     }
 
-    pub fn prev_page(&self) -> Result<ArtListResp, Box<dyn std::error::Error>> {
-        let page = if self.page == 1 { 1 } else { self.page - 1 };
-        match &self.method {
-            Method::Page => Self::list_by_page(page),
-            Method::Dynasty(dynasty) => Self::list_by_dynasty(page, dynasty.clone()),
-            Method::Writer(writer) => Self::list_by_writer(page, writer.clone()),
-            Method::Keyword(keyword) => Self::list_by_keyword(page, keyword.clone()),
-        }
-    }
-
-    pub fn next_page(&self) -> Result<ArtListResp, Box<dyn std::error::Error>> {
-        let page = self.page + 1;
-        match &self.method {
+    /// 按检索方式获取指定页
+    pub fn list(method: &Method, page: usize) -> Result<ArtListResp, Box<dyn std::error::Error>> {
+        match method {
             Method::Page => Self::list_by_page(page),
             Method::Dynasty(dynasty) => Self::list_by_dynasty(page, dynasty.clone()),
             Method::Writer(writer) => Self::list_by_writer(page, writer.clone()),
             Method::Keyword(keyword) => Self::list_by_keyword(page, keyword.clone()),
+            _ => Err(Box::new(APIError::LocalOnly)),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Sentence {
     pub id: String,
     pub name: String,
@@ -153,7 +263,7 @@ impl Sentence {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SentenceListResp {
     pub total: usize,
     pub pages: usize,
@@ -179,7 +289,7 @@ impl SentenceListResp {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Writer {
     pub id: String,
     pub name: String,
@@ -202,7 +312,7 @@ impl Writer {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WriterListResp {
     pub total: usize,
     pub pages: usize,
@@ -231,11 +341,24 @@ impl WriterListResp {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_with_query() {
+        let method = Method::Writer(String::new()).with_query(" 李白 ");
+        assert!(matches!(method, Method::Writer(ref w) if w == "李白"));
+        let method = Method::Collection("唐诗".to_string()).with_query("李白");
+        assert!(matches!(method, Method::Collection(ref c) if c == "唐诗"));
+        assert!(matches!(
+            ArtListResp::list(&Method::Tag("思乡".to_string()), 1),
+            Err(e) if matches!(e.downcast_ref(), Some(APIError::LocalOnly))
+        ));
+    }
+
     #[test]
     fn test_show_art_exit() {
         let art = ArticleSimple {
             id: "5b9a0136367d5c96f4cd2952".to_owned(),
             title: "将进酒".to_owned(),
+            snippet: None,
         };
         match art.show() {
             Err(_) => panic!(),
@@ -243,11 +366,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_paginate_local() {
+        let found: Vec<ArticleSimple> = (0..25)
+            .map(|i| ArticleSimple {
+                id: i.to_string(),
+                title: String::new(),
+                snippet: None,
+            })
+            .collect();
+        let resp = ArtListResp::paginate(found.clone(), 3, Method::Rhyme("下平十一尤".to_string()));
+        assert_eq!(resp.total, 25);
+        assert_eq!(resp.pages, 3);
+        assert_eq!(resp.data.len(), 5);
+        assert_eq!(resp.data[0].id, "20");
+        assert!(ArtListResp::paginate(found, 4, Method::Page)
+            .data
+            .is_empty());
+    }
+
     #[test]
     fn test_show_art_nonexit() {
         let art = ArticleSimple {
             id: "fake".to_owned(),
             title: "将进酒".to_owned(),
+            snippet: None,
         };
         match art.show() {
             Ok(_) => panic!(),
@@ -0,0 +1,1138 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use rand::Rng;
+use serde::Serialize;
+use std::error::Error;
+use std::io::{BufReader, Cursor, IsTerminal};
+use std::path::PathBuf;
+
+use crate::anki;
+use crate::api::{
+    APIError, ArtListResp, Article, ArticleSimple, Method, Sentence, SentenceListResp,
+    WriterListResp,
+};
+use crate::collection::{Collection, Library, Progress, Status, Summary};
+use crate::concordance;
+use crate::corpus::Corpus;
+use crate::discover::Discoverer;
+use crate::export;
+use crate::favorites::Favorites;
+use crate::fortune::{self, Fortune};
+use crate::note::Notes;
+use crate::practice;
+use crate::reading::{self, ReadingOverrides};
+use crate::related::Recommender;
+use crate::script::{Converter, Script};
+use crate::tag::Tags;
+use crate::theme::{Lexicon, ThemeError};
+
+/// 退出码: 成功, 没有结果, 参数错误, 网络错误, 本地文件或数据错误
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_NOT_FOUND: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NETWORK: i32 = 3;
+pub const EXIT_LOCAL: i32 = 4;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "enlighten",
+    version,
+    about = "古诗文阅读, 不带子命令时进入交互界面"
+)]
+pub struct Cli {
+    /// 以 JSON 格式输出
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// 随机数种子, 指定后随机结果可复现
+    #[arg(long, global = true)]
+    pub seed: Option<u64>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 按 id 显示文章
+    Show { id: String },
+    /// 按作者, 朝代或关键字列出文章, 都不指定时按页列出全部文章
+    Search {
+        #[arg(long, group = "method")]
+        writer: Option<String>,
+        #[arg(long, group = "method")]
+        dynasty: Option<String>,
+        #[arg(long, group = "method")]
+        keyword: Option<String>,
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// 随机显示一篇文章, 可限定作者, 朝代或关键字, 会避开最近看过的文章
+    Random {
+        #[arg(long, group = "method")]
+        writer: Option<String>,
+        #[arg(long, group = "method")]
+        dynasty: Option<String>,
+        #[arg(long, group = "method")]
+        keyword: Option<String>,
+    },
+    /// 列出名句
+    Sentence {
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// 列出作者, 指定姓名时列出其作品
+    Writer {
+        name: Option<String>,
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// 从本地缓存中随机输出一条名句或一首短诗, 适合放在 shell 启动脚本中
+    Fortune {
+        /// 不访问网络, 只使用本地缓存
+        #[arg(long)]
+        offline: bool,
+        /// 输出一首短诗而不是名句
+        #[arg(long)]
+        poem: bool,
+        #[arg(long)]
+        writer: Option<String>,
+        #[arg(long)]
+        dynasty: Option<String>,
+        /// 不计标点的最大字数
+        #[arg(long)]
+        length: Option<usize>,
+        /// 最大显示宽度, 0 表示不折行
+        #[arg(long, default_value_t = 40)]
+        width: usize,
+        #[arg(long, value_enum, default_value_t = Color::Auto)]
+        color: Color,
+    },
+    /// 将指定文章, 收藏或检索结果导出为 Markdown, HTML, EPUB, LaTeX 或 Typst
+    Export {
+        /// 文章 id, 可指定多个
+        ids: Vec<String>,
+        /// 导出收藏的文章
+        #[arg(long, conflicts_with = "ids")]
+        favorites: bool,
+        #[arg(long, group = "method", conflicts_with_all = ["ids", "favorites"])]
+        writer: Option<String>,
+        #[arg(long, group = "method", conflicts_with_all = ["ids", "favorites"])]
+        dynasty: Option<String>,
+        #[arg(long, group = "method", conflicts_with_all = ["ids", "favorites"])]
+        keyword: Option<String>,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Md)]
+        format: ExportFormat,
+        /// 多篇文章时的合集标题
+        #[arg(long)]
+        title: Option<String>,
+        /// 输出文件, 默认在当前目录下按标题命名
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// 附带个人笔记
+        #[arg(long)]
+        notes: bool,
+    },
+    /// 将指定文章或收藏制成 Anki 可导入的制表符分隔文件
+    Anki {
+        /// 文章 id, 可指定多个
+        ids: Vec<String>,
+        /// 使用收藏的文章
+        #[arg(long, conflicts_with = "ids")]
+        favorites: bool,
+        /// 卡片模板, 可重复指定, 默认全部
+        #[arg(long = "template", value_enum)]
+        templates: Vec<CardTemplate>,
+        /// 名句卡片使用全部缓存的名句, 默认只用出处为所选文章的名句
+        #[arg(long)]
+        all_sentences: bool,
+        #[arg(long, default_value = "古诗文")]
+        deck: String,
+        /// 输出文件, 默认输出到标准输出
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// 按文章正文生成田字格或米字格字帖, 每页一个 SVG 文件
+    Practice {
+        id: String,
+        #[arg(long, value_enum, default_value_t = SheetGrid::Tian)]
+        grid: SheetGrid,
+        /// 练习格留空, 不写浅色描红字
+        #[arg(long)]
+        no_trace: bool,
+        /// 不加拼音格
+        #[arg(long)]
+        no_pinyin: bool,
+        /// 每个字在范字之后的练习次数
+        #[arg(long, default_value_t = practice::COLUMNS - 1)]
+        repeat: usize,
+        /// 输出目录, 默认为当前目录
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// 浏览选集, 标记背诵状态, 导入或导出选集文件
+    Collection {
+        #[command(subcommand)]
+        command: CollectionCommand,
+    },
+    /// 查看, 添加或检索个人笔记
+    Note {
+        #[command(subcommand)]
+        command: NoteCommand,
+    },
+    /// 给文章打标签, 按标签浏览, 导入或导出标签文件
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+    /// 按内置词表自动判定本地文章的题材
+    Theme {
+        #[command(subcommand)]
+        command: ThemeCommand,
+    },
+    /// 列出本地文集中含有某字词的每一句, 关键词居中对齐
+    Concordance {
+        keyword: String,
+        #[arg(long, value_enum, default_value_t = HitOrder::Corpus)]
+        sort: HitOrder,
+        /// 关键词两侧各显示的字数
+        #[arg(long, default_value_t = 10)]
+        width: usize,
+    },
+    /// 按文本相似度, 作者与标签在本地文集中推荐相关作品
+    Related {
+        id: String,
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CollectionCommand {
+    /// 列出选集及完成度
+    List,
+    /// 按页列出选集中的文章及背诵状态
+    Show {
+        name: String,
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// 标记文章的背诵状态
+    Mark {
+        id: String,
+        #[arg(value_enum)]
+        status: StudyStatus,
+    },
+    /// 导入 JSON 格式的选集文件, 替换同名选集
+    Import { file: PathBuf },
+    /// 导出选集文件, 默认输出到标准输出
+    Export {
+        name: String,
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NoteCommand {
+    /// 列出文章的笔记
+    List { id: String },
+    /// 为文章添加笔记, 指定句序号(从 1 开始)时针对该句
+    Add {
+        id: String,
+        text: String,
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        line: Option<u32>,
+    },
+    /// 检索全部笔记, 多个词须同时出现
+    Search { query: Vec<String> },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TagCommand {
+    /// 列出全部标签及文章数
+    List,
+    /// 按页列出带有该标签的文章
+    Show {
+        tag: String,
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// 给文章加上标签, 可指定多个
+    Add {
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// 去掉文章的标签
+    Remove { id: String, tag: String },
+    /// 导入标签文件, 与已有的标签合并
+    Import { file: PathBuf },
+    /// 导出标签文件, 默认输出到标准输出
+    Export {
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ThemeCommand {
+    /// 列出题材及本地文集中的文章数
+    List,
+    /// 按页列出属于任一题材的本地文章, 题材以空格或顿号分隔
+    Show {
+        query: Vec<String>,
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+    /// 判定文章的题材并列出依据
+    Classify { id: String },
+    /// 列出与文章题材相同的本地文章
+    Like {
+        id: String,
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+    },
+}
+
+/// 索引排序方式, 对应 `concordance::Order`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum HitOrder {
+    Corpus,
+    Dynasty,
+    Writer,
+}
+
+impl From<HitOrder> for concordance::Order {
+    fn from(order: HitOrder) -> concordance::Order {
+        match order {
+            HitOrder::Corpus => concordance::Order::Corpus,
+            HitOrder::Dynasty => concordance::Order::Dynasty,
+            HitOrder::Writer => concordance::Order::Writer,
+        }
+    }
+}
+
+/// 导出文件格式, 对应 `export::Format`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Md,
+    Html,
+    Epub,
+    Tex,
+    Typst,
+}
+
+impl From<ExportFormat> for export::Format {
+    fn from(format: ExportFormat) -> export::Format {
+        match format {
+            ExportFormat::Md => export::Format::Markdown,
+            ExportFormat::Html => export::Format::Html,
+            ExportFormat::Epub => export::Format::Epub,
+            ExportFormat::Tex => export::Format::Latex,
+            ExportFormat::Typst => export::Format::Typst,
+        }
+    }
+}
+
+/// Anki 卡片模板, 对应 `anki::Template`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum CardTemplate {
+    NextLine,
+    TitleFirstLine,
+    SentenceSource,
+}
+
+impl From<CardTemplate> for anki::Template {
+    fn from(template: CardTemplate) -> anki::Template {
+        match template {
+            CardTemplate::NextLine => anki::Template::NextLine,
+            CardTemplate::TitleFirstLine => anki::Template::TitleFirstLine,
+            CardTemplate::SentenceSource => anki::Template::SentenceSource,
+        }
+    }
+}
+
+/// 字帖格子, 对应 `practice::Grid`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SheetGrid {
+    Tian,
+    Mi,
+}
+
+impl From<SheetGrid> for practice::Grid {
+    fn from(grid: SheetGrid) -> practice::Grid {
+        match grid {
+            SheetGrid::Tian => practice::Grid::Tian,
+            SheetGrid::Mi => practice::Grid::Mi,
+        }
+    }
+}
+
+/// 背诵状态, 对应 `collection::Status`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum StudyStatus {
+    New,
+    Learning,
+    Memorized,
+}
+
+impl From<StudyStatus> for Status {
+    fn from(status: StudyStatus) -> Status {
+        match status {
+            StudyStatus::New => Status::New,
+            StudyStatus::Learning => Status::Learning,
+            StudyStatus::Memorized => Status::Memorized,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// 输出到终端且未设置 NO_COLOR 时着色
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Color::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+/// 执行子命令, 返回退出码
+pub fn run(cli: Cli) -> i32 {
+    let json = cli.json;
+    let command = match cli.command {
+        Some(command) => command,
+        None => return EXIT_USAGE,
+    };
+    let result = match command {
+        Command::Show { id } => show(&id, json),
+        Command::Search {
+            writer,
+            dynasty,
+            keyword,
+            page,
+        } => search(writer, dynasty, keyword, page, json),
+        Command::Random {
+            writer,
+            dynasty,
+            keyword,
+        } => random(method_of(writer, dynasty, keyword), cli.seed, json),
+        Command::Sentence { page } => sentence(page, json),
+        Command::Writer { name, page } => writer(name, page, json),
+        Command::Fortune {
+            offline,
+            poem,
+            writer,
+            dynasty,
+            length,
+            width,
+            color,
+        } => {
+            let opts = fortune::Options {
+                poem,
+                writer,
+                dynasty,
+                length,
+            };
+            fortune(&opts, offline, width, color, json)
+        }
+        Command::Export {
+            ids,
+            favorites,
+            writer,
+            dynasty,
+            keyword,
+            format,
+            title,
+            output,
+            notes,
+        } => {
+            let scope = if favorites {
+                ExportScope::Favorites
+            } else if !ids.is_empty() {
+                ExportScope::Ids(ids)
+            } else {
+                ExportScope::Results(method_of(writer, dynasty, keyword))
+            };
+            export_articles(scope, format.into(), title, output, notes)
+        }
+        Command::Anki {
+            ids,
+            favorites,
+            templates,
+            all_sentences,
+            deck,
+            output,
+        } => {
+            let scope = if favorites {
+                Some(ExportScope::Favorites)
+            } else if !ids.is_empty() {
+                Some(ExportScope::Ids(ids))
+            } else {
+                None
+            };
+            export_anki(scope, templates, all_sentences, deck, output)
+        }
+        Command::Practice {
+            id,
+            grid,
+            no_trace,
+            no_pinyin,
+            repeat,
+            output,
+        } => {
+            let opts = practice::Options {
+                grid: grid.into(),
+                trace: !no_trace,
+                pinyin: !no_pinyin,
+                repeat,
+            };
+            practice_sheet(&id, &opts, output)
+        }
+        Command::Collection { command } => collection(command, json),
+        Command::Note { command } => note(command, json),
+        Command::Tag { command } => tag(command, json),
+        Command::Theme { command } => theme(command, json),
+        Command::Concordance {
+            keyword,
+            sort,
+            width,
+        } => concordance(&keyword, sort.into(), width, json),
+        Command::Related { id, limit } => related(&id, limit, json),
+    };
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit_code(e.as_ref())
+        }
+    }
+}
+
+fn exit_code(e: &(dyn Error + 'static)) -> i32 {
+    match e.downcast_ref::<APIError>() {
+        Some(APIError::NotFound) => EXIT_NOT_FOUND,
+        Some(APIError::InvalidPage) | Some(APIError::LocalOnly) => EXIT_USAGE,
+        None if e.is::<ThemeError>() || e.is::<regex::Error>() => EXIT_USAGE,
+        None if e.is::<reqwest::Error>() => EXIT_NETWORK,
+        None => EXIT_LOCAL,
+    }
+}
+
+fn output<T: Serialize>(value: &T, json: bool, plain: String) -> Result<(), Box<dyn Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        print!("{}", plain);
+    }
+    Ok(())
+}
+
+/// 按显示设置转换简繁
+fn localize(text: String) -> String {
+    match Script::load() {
+        Script::Simplified => text,
+        script => Converter::bundled().convert(&text, script),
+    }
+}
+
+fn check_page(page: usize) -> Result<(), Box<dyn Error>> {
+    if page < 1 {
+        return Err(Box::new(APIError::InvalidPage));
+    }
+    Ok(())
+}
+
+fn format_article(art: &Article) -> String {
+    format!(
+        "{}\n{}\n\n{}\n",
+        art.title,
+        art.byline(),
+        art.content.trim_end()
+    )
+}
+
+fn show_article(
+    corpus: &mut Corpus,
+    art: &ArticleSimple,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let article = corpus.fetch(art)?;
+    output(&article, json, localize(format_article(&article)))
+}
+
+fn show(id: &str, json: bool) -> Result<(), Box<dyn Error>> {
+    let art = ArticleSimple {
+        id: id.to_string(),
+        ..Default::default()
+    };
+    // 接口对不存在的 id 返回无法解析的内容
+    show_article(&mut Corpus::load(), &art, json).map_err(|e| {
+        match e.downcast_ref::<reqwest::Error>() {
+            Some(re) if re.is_decode() => Box::new(APIError::NotFound),
+            _ => e,
+        }
+    })
+}
+
+fn method_of(writer: Option<String>, dynasty: Option<String>, keyword: Option<String>) -> Method {
+    let conv = Converter::bundled();
+    let simplify = |query: Option<String>| query.map(|q| conv.to_simplified(&q));
+    match (simplify(writer), simplify(dynasty), simplify(keyword)) {
+        (Some(writer), _, _) => Method::Writer(writer),
+        (_, Some(dynasty), _) => Method::Dynasty(dynasty),
+        (_, _, Some(keyword)) => Method::Keyword(keyword),
+        _ => Method::Page,
+    }
+}
+
+fn search(
+    writer: Option<String>,
+    dynasty: Option<String>,
+    keyword: Option<String>,
+    page: usize,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    check_page(page)?;
+    let resp = ArtListResp::list(&method_of(writer, dynasty, keyword), page)?;
+    print_list(&resp, json)
+}
+
+fn print_list(resp: &ArtListResp, json: bool) -> Result<(), Box<dyn Error>> {
+    if resp.data.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    eprintln!("第 {}/{} 页, 共 {} 篇", resp.page, resp.pages, resp.total);
+    let plain = resp
+        .data
+        .iter()
+        .map(|art| match &art.snippet {
+            Some(snippet) => format!("{}\t{}\t{}\n", art.id, art.title, snippet),
+            None => format!("{}\t{}\n", art.id, art.title),
+        })
+        .collect();
+    output(resp, json, localize(plain))
+}
+
+fn random(method: Method, seed: Option<u64>, json: bool) -> Result<(), Box<dyn Error>> {
+    let mut corpus = Corpus::load();
+    let local: Vec<ArticleSimple> = corpus.articles().iter().map(Article::to_simple).collect();
+    let (resp, idx) = Discoverer::new(seed).pick(|page| {
+        ArtListResp::list(&method, page).or_else(|e| match method {
+            // 离线时从本地文集中抽取
+            Method::Page if !local.is_empty() => {
+                Ok(ArtListResp::paginate(local.clone(), page, Method::Page))
+            }
+            _ => Err(e),
+        })
+    })?;
+    show_article(&mut corpus, &resp.data[idx], json)
+}
+
+enum ExportScope {
+    Ids(Vec<String>),
+    Favorites,
+    Results(Method),
+}
+
+/// 取出范围内的文章及默认标题, 检索结果最多取 `export::MAX_ARTICLES` 篇
+fn collect_articles(scope: ExportScope) -> Result<(Vec<Article>, String), Box<dyn Error>> {
+    let mut corpus = Corpus::load();
+    let (items, title) = match scope {
+        ExportScope::Ids(ids) => {
+            let items = ids
+                .into_iter()
+                .map(|id| ArticleSimple {
+                    id,
+                    ..Default::default()
+                })
+                .collect();
+            (items, "合集".to_string())
+        }
+        ExportScope::Favorites => (Favorites::load().items().to_vec(), "收藏".to_string()),
+        ExportScope::Results(method) => {
+            let items = export::collect_results(1, |page| ArtListResp::list(&method, page))?;
+            (items, format!("检索结果 {}", method.label()))
+        }
+    };
+    let articles = items
+        .iter()
+        .map(|art| corpus.fetch(art))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((articles, title))
+}
+
+fn export_articles(
+    scope: ExportScope,
+    format: export::Format,
+    title: Option<String>,
+    output: Option<PathBuf>,
+    with_notes: bool,
+) -> Result<(), Box<dyn Error>> {
+    let (articles, default_title) = collect_articles(scope)?;
+    if articles.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let title = match (&articles[..], title) {
+        ([art], None) => art.title.clone(),
+        (_, Some(title)) => title,
+        (_, None) => default_title,
+    };
+    let output =
+        output.unwrap_or_else(|| PathBuf::from(export::file_name(&title, format.extension())));
+    export::export(
+        &output,
+        &articles,
+        &title,
+        format,
+        &ReadingOverrides::load(),
+        with_notes.then(Notes::load).as_ref(),
+    )?;
+    eprintln!("已导出 {} 篇到 {}", articles.len(), output.display());
+    Ok(())
+}
+
+fn export_anki(
+    scope: Option<ExportScope>,
+    templates: Vec<CardTemplate>,
+    all_sentences: bool,
+    deck: String,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let articles = match scope {
+        Some(scope) => collect_articles(scope)?.0,
+        None => vec![],
+    };
+    let templates: Vec<anki::Template> = if templates.is_empty() {
+        anki::Template::ALL.to_vec()
+    } else {
+        templates.into_iter().map(anki::Template::from).collect()
+    };
+    let cached = fortune::load_sentences();
+    let sentences: Vec<Sentence> = if all_sentences {
+        cached
+    } else {
+        anki::related_sentences(&articles, &cached)
+            .into_iter()
+            .cloned()
+            .collect()
+    };
+    let cards = anki::cards(&articles, &sentences, &templates);
+    if cards.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let tsv = anki::to_tsv(&cards, &deck);
+    match output {
+        Some(path) => {
+            std::fs::write(&path, tsv)?;
+            eprintln!("已导出 {} 张卡片到 {}", cards.len(), path.display());
+        }
+        None => print!("{}", tsv),
+    }
+    Ok(())
+}
+
+fn practice_sheet(
+    id: &str,
+    opts: &practice::Options,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let (articles, _) = collect_articles(ExportScope::Ids(vec![id.to_string()]))?;
+    let art = articles.first().ok_or(APIError::NotFound)?;
+    let overrides = ReadingOverrides::load().get(&art.id);
+    let annotated = reading::annotate(&art.content, &overrides);
+    let pages = practice::render(&art.title, &annotated, opts);
+    if pages.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let dir = output.unwrap_or_else(|| PathBuf::from("."));
+    for path in practice::save(&dir, &art.title, &pages)? {
+        eprintln!("已生成 {}", path.display());
+    }
+    Ok(())
+}
+
+fn collection(command: CollectionCommand, json: bool) -> Result<(), Box<dyn Error>> {
+    let mut library = Library::load();
+    let mut progress = Progress::load();
+    match command {
+        CollectionCommand::List => {
+            let summaries: Vec<Summary> =
+                library.all().iter().map(|c| progress.summary(c)).collect();
+            let plain = summaries
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{}\t{}/{}\t{}%\n",
+                        s.name,
+                        s.memorized,
+                        s.total,
+                        s.percent()
+                    )
+                })
+                .collect();
+            output(&summaries, json, plain)
+        }
+        CollectionCommand::Show { name, page } => {
+            check_page(page)?;
+            let found = library.get(&name).ok_or(APIError::NotFound)?;
+            let resp = progress.page(&found, &mut Corpus::load(), page, |title| {
+                Ok(ArtListResp::list_by_keyword(1, title.to_string())?.data)
+            })?;
+            progress.save()?;
+            print_list(&resp, json)
+        }
+        CollectionCommand::Mark { id, status } => {
+            progress.set(&id, status.into());
+            progress.save()
+        }
+        CollectionCommand::Import { file } => {
+            let imported = Collection::read(&file)?;
+            eprintln!(
+                "已导入选集 {}, 共 {} 篇",
+                imported.name,
+                imported.items.len()
+            );
+            library.add(imported);
+            library.save()
+        }
+        CollectionCommand::Export { name, output } => {
+            let found = library.get(&name).ok_or(APIError::NotFound)?;
+            let text = found.to_json()?;
+            match output {
+                Some(path) => std::fs::write(path, text + "\n")?,
+                None => println!("{}", text),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn note(command: NoteCommand, json: bool) -> Result<(), Box<dyn Error>> {
+    let mut notes = Notes::load();
+    match command {
+        NoteCommand::List { id } => {
+            let own = notes.get(&id);
+            if own.is_empty() {
+                return Err(Box::new(APIError::NotFound));
+            }
+            // 本地没有的文章只显示句序
+            let poem = Corpus::load().get(&id).cloned().unwrap_or_default().poem();
+            let plain = own
+                .iter()
+                .enumerate()
+                .map(|(i, n)| format!("{}\t{}\t{}\n", i + 1, n.label(&poem), n.text))
+                .collect();
+            output(&own, json, plain)
+        }
+        NoteCommand::Add { id, text, line } => {
+            notes.add(&id, line.map(|n| n as usize - 1), &text);
+            notes.save()
+        }
+        NoteCommand::Search { query } => {
+            let found = notes.search(&query.join(" "));
+            if found.is_empty() {
+                return Err(Box::new(APIError::NotFound));
+            }
+            let plain = found
+                .iter()
+                .map(|(id, n)| format!("{}\t{}\n", id, n.text))
+                .collect();
+            output(&found, json, plain)
+        }
+    }
+}
+
+fn tag(command: TagCommand, json: bool) -> Result<(), Box<dyn Error>> {
+    let mut tags = Tags::load();
+    match command {
+        TagCommand::List => {
+            let names = tags.names();
+            let plain = names
+                .iter()
+                .map(|(name, count)| format!("{}\t{}\n", name, count))
+                .collect();
+            output(&names, json, plain)
+        }
+        TagCommand::Show { tag, page } => {
+            check_page(page)?;
+            let resp = tags.page(&tag, page);
+            if resp.data.is_empty() {
+                return Err(Box::new(APIError::NotFound));
+            }
+            print_list(&resp, json)
+        }
+        TagCommand::Add { id, tags: names } => {
+            let (articles, _) = collect_articles(ExportScope::Ids(vec![id]))?;
+            let art = articles.first().ok_or(APIError::NotFound)?.to_simple();
+            for name in names.iter() {
+                tags.add(&art, name);
+            }
+            tags.save()
+        }
+        TagCommand::Remove { id, tag } => {
+            if !tags.remove(&id, &tag) {
+                return Err(Box::new(APIError::NotFound));
+            }
+            tags.save()
+        }
+        TagCommand::Import { file } => {
+            let added = tags.merge(Tags::read(&file)?);
+            eprintln!("已导入 {} 个标签", added);
+            tags.save()
+        }
+        TagCommand::Export { output } => {
+            let text = tags.to_json()?;
+            match output {
+                Some(path) => std::fs::write(path, text + "\n")?,
+                None => println!("{}", text),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn theme(command: ThemeCommand, json: bool) -> Result<(), Box<dyn Error>> {
+    let lexicon = Lexicon::bundled();
+    let corpus = Corpus::load();
+    let (query, page) = match command {
+        ThemeCommand::List => {
+            let counts = lexicon.counts(&corpus);
+            let plain = counts
+                .iter()
+                .map(|(name, count)| format!("{}\t{}\n", name, count))
+                .collect();
+            return output(&counts, json, plain);
+        }
+        ThemeCommand::Classify { id } => {
+            let (articles, _) = collect_articles(ExportScope::Ids(vec![id]))?;
+            let art = articles.first().ok_or(APIError::NotFound)?;
+            let labels = lexicon.classify(art);
+            let plain = labels
+                .iter()
+                .map(|l| format!("{}\t{}\t{}\n", l.theme, l.score, l.evidence.join("、")))
+                .collect();
+            return output(&labels, json, plain);
+        }
+        ThemeCommand::Show { query, page } => (query.join(" "), page),
+        ThemeCommand::Like { id, page } => {
+            let art = corpus.get(&id).ok_or(APIError::NotFound)?;
+            let names: Vec<String> = lexicon.classify(art).into_iter().map(|l| l.theme).collect();
+            if names.is_empty() {
+                return Err(Box::new(APIError::NotFound));
+            }
+            (names.join(" "), page)
+        }
+    };
+    check_page(page)?;
+    let found = lexicon.search(&corpus, &query)?;
+    if found.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    print_list(
+        &ArtListResp::paginate(found, page, Method::Theme(query)),
+        json,
+    )
+}
+
+fn concordance(
+    keyword: &str,
+    order: concordance::Order,
+    width: usize,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let keyword = Converter::bundled().to_simplified(keyword);
+    let mut hits = concordance::search(&Corpus::load(), &keyword);
+    if hits.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    concordance::sort(&mut hits, order);
+    let plain = hits
+        .iter()
+        .map(|hit| {
+            format!(
+                "{}\t{}\t{}:{}\n",
+                hit.kwic(width),
+                hit.source(),
+                hit.id,
+                hit.line + 1
+            )
+        })
+        .collect();
+    output(&hits, json, plain)
+}
+
+fn related(id: &str, limit: usize, json: bool) -> Result<(), Box<dyn Error>> {
+    let corpus = Corpus::load();
+    if corpus.get(id).is_none() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let found = Recommender::default().related(&corpus, &Tags::load(), id, limit);
+    if found.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let plain = found
+        .iter()
+        .map(|r| format!("{}\t{}\t{}\n", r.id, r.title, r.reasons.join(" · ")))
+        .collect();
+    output(&found, json, plain)
+}
+
+fn sentence(page: usize, json: bool) -> Result<(), Box<dyn Error>> {
+    check_page(page)?;
+    let resp = SentenceListResp::list(page)?;
+    if resp.data.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let plain = resp
+        .data
+        .iter()
+        .map(|s| format!("{}\t{}\t{}\n", s.id, s.name.trim(), s.from))
+        .collect();
+    output(&resp, json, localize(plain))
+}
+
+fn writer(name: Option<String>, page: usize, json: bool) -> Result<(), Box<dyn Error>> {
+    check_page(page)?;
+    if let Some(name) = name {
+        let name = Converter::bundled().to_simplified(&name);
+        return print_list(&ArtListResp::list_by_writer(page, name)?, json);
+    }
+    let resp = WriterListResp::list(page)?;
+    if resp.data.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let plain = resp
+        .data
+        .iter()
+        .map(|w| format!("{}\t{}\t{}\n", w.id, w.name, w.simple_intro.trim()))
+        .collect();
+    output(&resp, json, localize(plain))
+}
+
+fn fortune(
+    opts: &fortune::Options,
+    offline: bool,
+    width: usize,
+    color: Color,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut sentences = fortune::load_sentences();
+    let mut rng = rand::thread_rng();
+    let mut picked = pick_fortune(&sentences, opts, &mut rng)?;
+    // 本地没有合适的名句时才联网取一页并缓存
+    if picked.is_none() && !offline && !opts.poem {
+        let pages = SentenceListResp::list(1)?.pages.max(1);
+        let resp = SentenceListResp::list(rng.gen_range(1..=pages))?;
+        fortune::save_sentences(&mut sentences, resp.data);
+        picked = pick_fortune(&sentences, opts, &mut rng)?;
+    }
+    let mut picked = picked.ok_or(APIError::NotFound)?;
+    let script = Script::load();
+    if script != Script::Simplified {
+        let conv = Converter::bundled();
+        picked = Fortune {
+            text: conv.convert(&picked.text, script),
+            from: conv.convert(&picked.from, script),
+        };
+    }
+    output(
+        &picked,
+        json,
+        format_fortune(&picked, width, color.enabled()),
+    )
+}
+
+fn pick_fortune<R: Rng>(
+    sentences: &[Sentence],
+    opts: &fortune::Options,
+    rng: &mut R,
+) -> Result<Option<Fortune>, Box<dyn Error>> {
+    let picked = match Corpus::open_raw() {
+        Ok(file) => fortune::pick(BufReader::new(file), sentences, opts, rng)?,
+        // 还没有缓存过文章
+        Err(_) => fortune::pick(Cursor::new(&[][..]), sentences, opts, rng)?,
+    };
+    Ok(picked)
+}
+
+/// 正文折行输出, 出处在下一行右对齐
+fn format_fortune(fortune: &Fortune, width: usize, color: bool) -> String {
+    let lines = fortune::wrap(&fortune.text, width);
+    let from = format!("—— {}", fortune.from);
+    let text_width = lines
+        .iter()
+        .map(|l| fortune::display_width(l))
+        .max()
+        .unwrap_or(0);
+    let pad = text_width.saturating_sub(fortune::display_width(&from));
+    let mut out = String::new();
+    for line in lines {
+        if color {
+            out.push_str(&format!("\x1b[1m{}\x1b[0m\n", line));
+        } else {
+            out.push_str(&format!("{}\n", line));
+        }
+    }
+    out.push_str(&" ".repeat(pad));
+    if color {
+        out.push_str(&format!("\x1b[36m{}\x1b[0m\n", from));
+    } else {
+        out.push_str(&format!("{}\n", from));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let cli =
+            Cli::try_parse_from(["enlighten", "search", "--writer", "李白", "--json"]).unwrap();
+        assert!(cli.json);
+        match cli.command {
+            Some(Command::Search { writer, page, .. }) => {
+                assert_eq!(writer.as_deref(), Some("李白"));
+                assert_eq!(page, 1);
+            }
+            _ => panic!(),
+        }
+        assert!(
+            Cli::try_parse_from(["enlighten", "search", "--writer", "a", "--dynasty", "b"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["enlighten"])
+            .unwrap()
+            .command
+            .is_none());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&APIError::NotFound), EXIT_NOT_FOUND);
+        assert_eq!(exit_code(&APIError::InvalidPage), EXIT_USAGE);
+        let missing: Box<dyn Error> = Box::new(APIError::NotFound);
+        assert_eq!(exit_code(missing.as_ref()), EXIT_NOT_FOUND);
+        assert_eq!(
+            check_page(0).map_err(|e| exit_code(e.as_ref())),
+            Err(EXIT_USAGE)
+        );
+        let unknown = ThemeError::Unknown("咏史".to_string());
+        assert_eq!(exit_code(&unknown), EXIT_USAGE);
+        let pattern = crate::search::LinePattern::parse("/[/").unwrap_err();
+        assert_eq!(exit_code(&pattern), EXIT_USAGE);
+        let io = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(exit_code(&io), EXIT_LOCAL);
+        let json = serde_json::from_str::<Collection>("{").unwrap_err();
+        assert_eq!(exit_code(&json), EXIT_LOCAL);
+    }
+}
@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::api::{APIError, ArtListResp, Article, ArticleSimple, Method, LOCAL_PAGE_SIZE};
+use crate::corpus::Corpus;
+use crate::store;
+
+const COLLECTIONS_FILE: &str = "collections.json";
+const PROGRESS_FILE: &str = "progress.json";
+const BUILTIN: [&str; 1] = [include_str!("../assets/collections/tangshi-wujue.json")];
+
+/// 选集中的一篇, 以 id 指定, 或以标题与作者指定
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub writer: Option<String>,
+}
+
+impl Item {
+    /// 记录解析结果时使用的键
+    fn key(&self) -> String {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => format!(
+                "{}《{}》",
+                self.writer.as_deref().unwrap_or(""),
+                self.title.as_deref().unwrap_or("")
+            ),
+        }
+    }
+
+    fn matches(&self, art: &Article) -> bool {
+        match &self.id {
+            Some(id) => &art.id == id,
+            None => {
+                self.title.as_deref() == Some(art.title.as_str())
+                    && self.writer.as_ref().is_none_or(|w| w == &art.writer)
+            }
+        }
+    }
+}
+
+/// 有序的文章列表, 可以 JSON 文件分享
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Collection {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub items: Vec<Item>,
+}
+
+impl Collection {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Collection, Box<dyn std::error::Error>> {
+        let collection: Collection = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let valid = !collection.name.trim().is_empty()
+            && collection
+                .items
+                .iter()
+                .all(|item| item.id.is_some() || item.title.is_some());
+        if !valid {
+            return Err("选集须有名称, 且每篇须指定 id 或标题".into());
+        }
+        Ok(collection)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// 内置选集与用户导入的选集, 同名时用户的优先
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Library {
+    collections: Vec<Collection>,
+}
+
+impl Library {
+    pub fn load() -> Library {
+        store::load(COLLECTIONS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(COLLECTIONS_FILE, self)
+    }
+
+    pub fn all(&self) -> Vec<Collection> {
+        let mut all: Vec<Collection> = BUILTIN
+            .iter()
+            .filter_map(|text| serde_json::from_str(text).ok())
+            .filter(|c: &Collection| self.collections.iter().all(|u| u.name != c.name))
+            .collect();
+        all.extend(self.collections.iter().cloned());
+        all
+    }
+
+    pub fn get(&self, name: &str) -> Option<Collection> {
+        self.all().into_iter().find(|c| c.name == name)
+    }
+
+    /// 加入选集, 替换同名的旧选集
+    pub fn add(&mut self, collection: Collection) {
+        self.collections.retain(|c| c.name != collection.name);
+        self.collections.push(collection);
+    }
+}
+
+/// 背诵状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    New,
+    Learning,
+    Memorized,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::New, Status::Learning, Status::Memorized];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::New => "未学",
+            Status::Learning => "在学",
+            Status::Memorized => "已背",
+        }
+    }
+}
+
+/// 各篇文章的背诵状态, 以及选集条目解析到的文章 id
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    status: HashMap<String, Status>,
+    resolved: HashMap<String, String>,
+}
+
+/// 选集的完成情况
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub name: String,
+    pub description: String,
+    pub total: usize,
+    pub memorized: usize,
+}
+
+impl Summary {
+    pub fn percent(&self) -> usize {
+        (self.memorized * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+impl Progress {
+    pub fn load() -> Progress {
+        store::load(PROGRESS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(PROGRESS_FILE, self)
+    }
+
+    pub fn status(&self, id: &str) -> Status {
+        self.status.get(id).copied().unwrap_or(Status::New)
+    }
+
+    pub fn set(&mut self, id: &str, status: Status) {
+        if status == Status::New {
+            self.status.remove(id);
+        } else {
+            self.status.insert(id.to_string(), status);
+        }
+    }
+
+    fn id_of(&self, item: &Item) -> Option<String> {
+        item.id
+            .clone()
+            .or_else(|| self.resolved.get(&item.key()).cloned())
+    }
+
+    pub fn summary(&self, collection: &Collection) -> Summary {
+        let memorized = collection
+            .items
+            .iter()
+            .filter_map(|item| self.id_of(item))
+            .filter(|id| self.status(id) == Status::Memorized)
+            .count();
+        Summary {
+            name: collection.name.clone(),
+            description: collection.description.clone(),
+            total: collection.items.len(),
+            memorized,
+        }
+    }
+
+    /// 在本地文集中查找条目, 找不到时用 `search` 按标题检索并逐篇核对作者
+    fn resolve<F>(&mut self, item: &Item, corpus: &mut Corpus, search: &mut F) -> Option<String>
+    where
+        F: FnMut(&str) -> Result<Vec<ArticleSimple>, Box<dyn std::error::Error>>,
+    {
+        if let Some(id) = self.id_of(item) {
+            return Some(id);
+        }
+        let title = item.title.as_deref()?;
+        let found = match corpus.articles().iter().find(|art| item.matches(art)) {
+            Some(art) => Some(art.id.clone()),
+            None => {
+                let candidates = search(title).unwrap_or_else(|e| {
+                    log::warn!("failed to search {}: {}", title, e);
+                    vec![]
+                });
+                candidates
+                    .iter()
+                    .filter(|c| c.title == title)
+                    .filter_map(|c| corpus.fetch(c).ok())
+                    .find(|art| item.matches(art))
+                    .map(|art| art.id)
+            }
+        };
+        if let Some(id) = &found {
+            self.resolved.insert(item.key(), id.clone());
+        }
+        found
+    }
+
+    /// 按页列出选集, 只解析该页的条目, 匹配处为背诵状态, 未能找到的条目 id 为空
+    pub fn page<F>(
+        &mut self,
+        collection: &Collection,
+        corpus: &mut Corpus,
+        page: usize,
+        mut search: F,
+    ) -> Result<ArtListResp, Box<dyn std::error::Error>>
+    where
+        F: FnMut(&str) -> Result<Vec<ArticleSimple>, Box<dyn std::error::Error>>,
+    {
+        if page < 1 {
+            return Err(Box::new(APIError::InvalidPage));
+        }
+        let on_page = (page - 1) * LOCAL_PAGE_SIZE..page * LOCAL_PAGE_SIZE;
+        let ids: Vec<Option<String>> = collection
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                if on_page.contains(&i) {
+                    self.resolve(item, corpus, &mut search)
+                } else {
+                    self.id_of(item)
+                }
+            })
+            .collect();
+        let summary = self.summary(collection);
+        let mut found = vec![];
+        for (i, (item, id)) in collection.items.iter().zip(ids).enumerate() {
+            let title = id
+                .as_ref()
+                .and_then(|id| corpus.get(id))
+                .map(|art| art.title.clone())
+                .or_else(|| item.title.clone())
+                .unwrap_or_default();
+            let status = match &id {
+                Some(id) => self.status(id).name(),
+                None => "未找到",
+            };
+            found.push(ArticleSimple {
+                id: id.unwrap_or_default(),
+                title,
+                snippet: Some(format!(
+                    "{} · 第 {}/{} 篇 · 已背 {}%",
+                    status,
+                    i + 1,
+                    summary.total,
+                    summary.percent()
+                )),
+            });
+        }
+        let method = Method::Collection(collection.name.clone());
+        Ok(ArtListResp::paginate(found, page, method))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(id: &str, title: &str, writer: &str) -> Article {
+        Article {
+            id: id.to_string(),
+            title: title.to_string(),
+            writer: writer.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn item(title: &str, writer: &str) -> Item {
+        Item {
+            title: Some(title.to_string()),
+            writer: Some(writer.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_builtin_and_import() {
+        let mut library = Library::default();
+        let builtin = library.all();
+        assert_eq!(builtin[0].items.len(), 29);
+        let mut custom = builtin[0].clone();
+        custom.items.truncate(2);
+        library.add(custom.clone());
+        assert_eq!(library.all().len(), 1);
+        assert_eq!(library.get(&custom.name).unwrap().items.len(), 2);
+
+        let json = custom.to_json().unwrap();
+        assert!(json.contains("\"title\": \"鹿柴\""));
+        assert!(!json.contains("\"id\""));
+        let parsed: Collection = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, custom);
+    }
+
+    #[test]
+    fn test_page_and_progress() {
+        let mut corpus = Corpus::default();
+        corpus.insert(article("1", "静夜思", "李白")).unwrap();
+        let collection = Collection {
+            name: "测试".to_string(),
+            items: vec![
+                item("静夜思", "李白"),
+                Item {
+                    id: Some("9".to_string()),
+                    ..Default::default()
+                },
+                item("春晓", "孟浩然"),
+                item("春晓", "无名氏"),
+            ],
+            ..Default::default()
+        };
+        let mut progress = Progress::default();
+        progress.set("1", Status::Memorized);
+        let mut searched = vec![];
+        let resp = progress
+            .page(&collection, &mut corpus, 1, |title| {
+                searched.push(title.to_string());
+                Ok(vec![])
+            })
+            .unwrap();
+        assert_eq!(resp.total, 4);
+        assert!(matches!(resp.method, Method::Collection(ref name) if name == "测试"));
+        assert_eq!(resp.data[0].id, "1");
+        assert_eq!(
+            resp.data[0].snippet.as_deref(),
+            Some("已背 · 第 1/4 篇 · 已背 25%")
+        );
+        assert_eq!(resp.data[1].id, "9");
+        // 本地没有的按标题检索, 检索不到时 id 为空
+        assert_eq!(searched, vec!["春晓", "春晓"]);
+        assert_eq!(resp.data[2].id, "");
+        assert!(resp.data[2]
+            .snippet
+            .as_deref()
+            .unwrap()
+            .starts_with("未找到"));
+
+        progress.set("9", Status::Memorized);
+        progress.set("1", Status::New);
+        let summary = progress.summary(&collection);
+        assert_eq!((summary.memorized, summary.percent()), (1, 25));
+        assert_eq!(progress.status("1"), Status::New);
+        assert!(progress
+            .page(&collection, &mut corpus, 0, |_| Ok(vec![]))
+            .is_err());
+    }
+}
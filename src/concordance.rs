@@ -0,0 +1,197 @@
+use pinyin::ToPinyin;
+use serde::Serialize;
+
+use crate::api;
+use crate::corpus::Corpus;
+use crate::poem::{self, Line};
+
+/// 朝代的先后, 未列出的排在最后
+const DYNASTIES: [&str; 13] = [
+    "先秦",
+    "两汉",
+    "魏晋",
+    "南北朝",
+    "隋代",
+    "唐代",
+    "五代",
+    "宋代",
+    "金朝",
+    "元代",
+    "明代",
+    "清代",
+    "近现代",
+];
+
+/// 一处出现: 关键词及其前后的上下文
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hit {
+    pub id: String,
+    pub title: String,
+    pub writer: String,
+    pub dynasty: String,
+    /// 所在句的句序号, 从 0 开始
+    pub line: usize,
+    /// 上一句与本句中关键词之前的部分
+    pub left: String,
+    pub keyword: String,
+    /// 本句中关键词之后的部分与下一句
+    pub right: String,
+}
+
+impl Hit {
+    /// 出处, 如 "〔唐代〕李白《静夜思》"
+    pub fn source(&self) -> String {
+        api::source(&self.dynasty, &self.writer, &self.title)
+    }
+
+    /// 关键词居中对齐的一行, 两侧各取 `width` 个字, 左侧不足时以全角空格补齐
+    pub fn kwic(&self, width: usize) -> String {
+        let left: Vec<char> = self.left.chars().collect();
+        let left: String = left[left.len().saturating_sub(width)..].iter().collect();
+        let pad = "　".repeat(width.saturating_sub(left.chars().count()));
+        let right: String = self.right.chars().take(width).collect();
+        format!("{}{}【{}】{}", pad, left, self.keyword, right)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    /// 文集中的先后
+    Corpus,
+    Dynasty,
+    Writer,
+}
+
+impl Order {
+    pub const ALL: [Order; 3] = [Order::Corpus, Order::Dynasty, Order::Writer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Order::Corpus => "原序",
+            Order::Dynasty => "朝代",
+            Order::Writer => "作者",
+        }
+    }
+}
+
+/// 列出本地文集中含有关键词的每一句, 一句中出现多次时各列一条
+pub fn search(corpus: &Corpus, keyword: &str) -> Vec<Hit> {
+    let keyword = poem::normalize(keyword);
+    if keyword.is_empty() {
+        return vec![];
+    }
+    let mut hits = vec![];
+    for art in corpus.articles() {
+        let poem = art.poem();
+        let lines: Vec<&Line> = poem.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            for (pos, _) in line.text.match_indices(&keyword) {
+                let before = match i.checked_sub(1) {
+                    Some(prev) => lines[prev].with_punct(),
+                    None => String::new(),
+                };
+                let after = lines.get(i + 1).map(|l| l.with_punct()).unwrap_or_default();
+                hits.push(Hit {
+                    id: art.id.clone(),
+                    title: art.title.clone(),
+                    writer: art.writer.clone(),
+                    dynasty: art.dynasty.clone(),
+                    line: line.index,
+                    left: before + &line.text[..pos],
+                    keyword: keyword.clone(),
+                    right: format!("{}{}", &line.with_punct()[pos + keyword.len()..], after),
+                });
+            }
+        }
+    }
+    hits
+}
+
+fn dynasty_rank(dynasty: &str) -> usize {
+    DYNASTIES
+        .iter()
+        .position(|d| *d == dynasty)
+        .unwrap_or(DYNASTIES.len())
+}
+
+/// 按拼音排序用的键, 不能转为拼音的字保持原样
+fn pinyin_key(text: &str) -> String {
+    text.chars()
+        .map(|c| match c.to_pinyin() {
+            Some(p) => format!("{} ", p.plain()),
+            None => c.to_string(),
+        })
+        .collect()
+}
+
+/// 排序, 同朝代或同作者的保持原来的先后
+pub fn sort(hits: &mut [Hit], order: Order) {
+    match order {
+        Order::Corpus => (),
+        Order::Dynasty => {
+            hits.sort_by_cached_key(|h| (dynasty_rank(&h.dynasty), pinyin_key(&h.writer)))
+        }
+        Order::Writer => {
+            hits.sort_by_cached_key(|h| (pinyin_key(&h.writer), dynasty_rank(&h.dynasty)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Article;
+    use crate::fixtures::jingyesi;
+
+    fn corpus() -> Corpus {
+        let mut corpus = Corpus::default();
+        corpus.insert(jingyesi()).unwrap();
+        for (id, title, writer, dynasty, content) in [
+            ("2", "水调歌头", "苏轼", "宋代", "明月几时有？把酒问青天。"),
+            ("3", "关雎", "佚名", "先秦", "关关雎鸠，在河之洲。"),
+        ] {
+            corpus
+                .insert(Article {
+                    id: id.to_string(),
+                    title: title.to_string(),
+                    writer: writer.to_string(),
+                    dynasty: dynasty.to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        corpus
+    }
+
+    #[test]
+    fn test_search() {
+        let corpus = corpus();
+        let hits = search(&corpus, "明月");
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].left, "床前");
+        assert_eq!(hits[0].right, "光，疑是地上霜。");
+        assert_eq!(hits[1].line, 2);
+        assert_eq!(hits[1].left, "疑是地上霜。举头望");
+        assert_eq!(hits[1].source(), "〔唐代〕李白《静夜思》");
+        assert_eq!(hits[1].kwic(4), "。举头望【明月】，低头思");
+        assert_eq!(hits[2].kwic(4), "　　　　【明月】几时有？");
+        // 一句中出现两次
+        assert_eq!(search(&corpus, "关").len(), 2);
+        assert!(search(&corpus, "，").is_empty());
+    }
+
+    #[test]
+    fn test_sort() {
+        let corpus = corpus();
+        let mut hits = search(&corpus, "明月");
+        hits.extend(search(&corpus, "关关"));
+        sort(&mut hits, Order::Dynasty);
+        let ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "1", "1", "2"]);
+        sort(&mut hits, Order::Writer);
+        let writers: Vec<&str> = hits.iter().map(|h| h.writer.as_str()).collect();
+        assert_eq!(writers, vec!["李白", "李白", "苏轼", "佚名"]);
+        assert_eq!(hits[0].line, 0);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::api::{Article, ArticleSimple};
+use crate::store::data_dir;
+
+const ARTICLES_FILE: &str = "articles.jsonl";
+
+/// 本地文集, 浏览过的文章会被缓存到数据目录下的 `articles.jsonl`
+#[derive(Debug, Default)]
+pub struct Corpus {
+    path: Option<PathBuf>,
+    articles: Vec<Article>,
+    ids: HashMap<String, usize>,
+}
+
+impl Corpus {
+    pub fn load() -> Corpus {
+        Corpus::open(data_dir().join(ARTICLES_FILE))
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Corpus {
+        let mut corpus = Corpus {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Default::default()
+        };
+        if let Ok(file) = File::open(path.as_ref()) {
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                match serde_json::from_str::<Article>(&line) {
+                    Ok(art) => corpus.push(art),
+                    Err(e) => log::warn!("skip broken corpus line: {}", e),
+                }
+            }
+        }
+        corpus
+    }
+
+    /// 打开缓存文件以便不经解析地逐行读取, 每行为一篇文章
+    pub fn open_raw() -> std::io::Result<File> {
+        File::open(data_dir().join(ARTICLES_FILE))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Article> {
+        self.ids.get(id).map(|&idx| &self.articles[idx])
+    }
+
+    pub fn articles(&self) -> &[Article] {
+        &self.articles
+    }
+
+    /// 加入文集并追加写入缓存文件, 已存在的文章会被忽略
+    pub fn insert(&mut self, art: Article) -> Result<(), Box<dyn std::error::Error>> {
+        if self.ids.contains_key(&art.id) {
+            return Ok(());
+        }
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&art)?)?;
+        }
+        self.push(art);
+        Ok(())
+    }
+
+    /// 优先读取缓存, 否则从网络获取并加入文集
+    pub fn fetch(&mut self, art: &ArticleSimple) -> Result<Article, Box<dyn std::error::Error>> {
+        if let Some(article) = self.get(&art.id) {
+            return Ok(article.clone());
+        }
+        let article = art.show()?;
+        if let Err(e) = self.insert(article.clone()) {
+            log::warn!("failed to cache article {}: {}", article.id, e);
+        }
+        Ok(article)
+    }
+
+    fn push(&mut self, art: Article) {
+        if !self.ids.contains_key(&art.id) {
+            self.ids.insert(art.id.clone(), self.articles.len());
+            self.articles.push(art);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(id: &str, content: &str) -> Article {
+        Article {
+            id: id.to_string(),
+            title: "静夜思".to_string(),
+            writer: "李白".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_corpus_insert_and_reload() {
+        let path =
+            std::env::temp_dir().join(format!("enlighten-corpus-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut corpus = Corpus::open(&path);
+        assert!(corpus.articles().is_empty());
+        corpus
+            .insert(article("1", "床前明月光，疑是地上霜。"))
+            .unwrap();
+        corpus
+            .insert(article("1", "床前明月光，疑是地上霜。"))
+            .unwrap();
+        corpus
+            .insert(article("2", "举头望明月，低头思故乡。"))
+            .unwrap();
+        assert_eq!(corpus.articles().len(), 2);
+
+        let corpus = Corpus::open(&path);
+        assert_eq!(corpus.articles().len(), 2);
+        assert_eq!(corpus.get("2").unwrap().content, "举头望明月，低头思故乡。");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use crate::api::{APIError, ArtListResp};
+use crate::store;
+
+const RECENT_FILE: &str = "recent.json";
+/// 记住最近看过的文章数
+const RECENT_LIMIT: usize = 100;
+/// 抽到最近看过的文章时重抽的次数, 用完后接受最后一次的结果
+const MAX_TRIES: usize = 8;
+
+/// 最近随机看过的文章
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Recent {
+    ids: VecDeque<String>,
+}
+
+impl Recent {
+    pub fn load() -> Recent {
+        store::load(RECENT_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(RECENT_FILE, self)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.iter().any(|i| i == id)
+    }
+
+    pub fn push(&mut self, id: &str) {
+        self.ids.retain(|i| i != id);
+        self.ids.push_back(id.to_string());
+        while self.ids.len() > RECENT_LIMIT {
+            self.ids.pop_front();
+        }
+    }
+}
+
+/// 随机选取文章, 指定种子时结果可复现, 此时只在本次运行中回避看过的文章
+#[derive(Debug)]
+pub struct Discoverer {
+    rng: StdRng,
+    recent: Recent,
+    persist: bool,
+}
+
+impl Discoverer {
+    pub fn new(seed: Option<u64>) -> Discoverer {
+        match seed {
+            Some(seed) => Discoverer {
+                rng: StdRng::seed_from_u64(seed),
+                recent: Recent::default(),
+                persist: false,
+            },
+            None => Discoverer {
+                rng: StdRng::from_entropy(),
+                recent: Recent::load(),
+                persist: true,
+            },
+        }
+    }
+
+    /// 在 `fetch` 给出的分页结果中随机选取一篇, 返回所在页及其在页中的序号
+    ///
+    /// 先取第一页得到 `total` 与 `pages`, 再在全部结果中均匀抽取位置, 只请求抽中的页
+    pub fn pick<F>(
+        &mut self,
+        mut fetch: F,
+    ) -> Result<(ArtListResp, usize), Box<dyn std::error::Error>>
+    where
+        F: FnMut(usize) -> Result<ArtListResp, Box<dyn std::error::Error>>,
+    {
+        let first = fetch(1)?;
+        if first.total == 0 || first.pages == 0 || first.data.is_empty() {
+            return Err(Box::new(APIError::NotFound));
+        }
+        let page_size = first.total.div_ceil(first.pages).max(1);
+        let mut pages: HashMap<usize, ArtListResp> = HashMap::new();
+        pages.insert(1, first);
+        let mut chosen = None;
+        for _ in 0..MAX_TRIES {
+            let pos = self.rng.gen_range(0..pages[&1].total);
+            let page = (pos / page_size + 1).min(pages[&1].pages);
+            if let Entry::Vacant(entry) = pages.entry(page) {
+                entry.insert(fetch(page)?);
+            }
+            // 最后一页可能不满, 接口返回的条数也可能与统计不符
+            let len = pages[&page].data.len();
+            if len == 0 {
+                continue;
+            }
+            let idx = (pos - (page - 1) * page_size).min(len - 1);
+            let seen = self.recent.contains(&pages[&page].data[idx].id);
+            chosen = Some((page, idx));
+            if !seen {
+                break;
+            }
+        }
+        let (page, idx) = chosen.ok_or(APIError::NotFound)?;
+        let resp = pages.remove(&page).unwrap();
+        self.recent.push(&resp.data[idx].id);
+        if self.persist {
+            if let Err(e) = self.recent.save() {
+                log::warn!("failed to save recent articles: {}", e);
+            }
+        }
+        Ok((resp, idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{ArticleSimple, Method};
+
+    fn fetch(total: usize) -> impl FnMut(usize) -> Result<ArtListResp, Box<dyn std::error::Error>> {
+        let found: Vec<ArticleSimple> = (0..total)
+            .map(|i| ArticleSimple {
+                id: i.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        move |page| Ok(ArtListResp::paginate(found.clone(), page, Method::Page))
+    }
+
+    fn picks(seed: u64, total: usize, n: usize) -> Vec<String> {
+        let mut discoverer = Discoverer::new(Some(seed));
+        let mut fetch = fetch(total);
+        (0..n)
+            .map(|_| {
+                let (resp, idx) = discoverer.pick(&mut fetch).unwrap();
+                resp.data[idx].id.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reproducible() {
+        assert_eq!(picks(42, 95, 10), picks(42, 95, 10));
+        assert_ne!(picks(42, 95, 10), picks(43, 95, 10));
+        assert!(picks(1, 95, 20)
+            .iter()
+            .all(|id| id.parse::<usize>().unwrap() < 95));
+    }
+
+    #[test]
+    fn test_avoid_recent() {
+        let mut ids = picks(7, 30, 20);
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 20);
+        let mut discoverer = Discoverer::new(Some(7));
+        assert!(discoverer.pick(fetch(0)).is_err());
+        // 只有一篇时看过也要返回
+        assert_eq!(picks(7, 1, 3), vec!["0", "0", "0"]);
+    }
+}
@@ -0,0 +1,527 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::api::{ArtListResp, Article, ArticleSimple};
+use crate::note::Notes;
+use crate::reading::ReadingOverrides;
+use crate::typeset;
+
+/// 导出检索结果时最多收录的篇数
+pub const MAX_ARTICLES: usize = 100;
+
+const STYLE: &str = "body { font-family: serif; line-height: 1.8; max-width: 40em; margin: 2em auto; padding: 0 1em; }
+h1, h2 { text-align: center; }
+.writer { text-align: center; color: #666; }
+.content p { text-align: center; margin: 0.2em 0; }
+nav li { margin: 0.3em 0; }
+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+    Epub,
+    /// 供打印的排版格式, 带拼音, 脚注与对照译文
+    Latex,
+    Typst,
+}
+
+impl Format {
+    pub const ALL: [Format; 5] = [
+        Format::Markdown,
+        Format::Html,
+        Format::Epub,
+        Format::Latex,
+        Format::Typst,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Markdown => "Markdown",
+            Format::Html => "HTML",
+            Format::Epub => "EPUB",
+            Format::Latex => "LaTeX",
+            Format::Typst => "Typst",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Epub => "epub",
+            Format::Latex => "tex",
+            Format::Typst => "typ",
+        }
+    }
+}
+
+/// 注释, 翻译与赏析中不为空的部分
+fn sections(art: &Article) -> Vec<(&'static str, &str)> {
+    [
+        ("注释", &art.remark),
+        ("翻译", &art.translation),
+        ("赏析", &art.shangxi),
+    ]
+    .iter()
+    .filter_map(|(name, text)| {
+        text.as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| (*name, t))
+    })
+    .collect()
+}
+
+/// 文章的笔记, 每条带上所指的位置
+fn note_lines(art: &Article, notes: &Notes) -> Vec<String> {
+    let poem = art.poem();
+    notes
+        .get(&art.id)
+        .iter()
+        .map(|n| format!("{}：{}", n.label(&poem), n.text))
+        .collect()
+}
+
+fn paragraphs(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|l| !l.is_empty())
+}
+
+/// 从第 `start` 页起逐页收集检索结果, 达到篇数上限或末页即停止翻页
+pub fn collect_results<E>(
+    start: usize,
+    mut fetch: impl FnMut(usize) -> Result<ArtListResp, E>,
+) -> Result<Vec<ArticleSimple>, E> {
+    let mut items = vec![];
+    let mut page = start.max(1);
+    loop {
+        let resp = fetch(page)?;
+        items.extend(resp.data);
+        if page >= resp.pages || items.len() >= MAX_ARTICLES {
+            break;
+        }
+        page += 1;
+    }
+    items.truncate(MAX_ARTICLES);
+    Ok(items)
+}
+
+/// 以标题为文件名, 去掉文件名中不能使用的字符
+pub fn file_name(title: &str, extension: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_whitespace() => '_',
+            c => c,
+        })
+        .collect();
+    format!("{}.{}", name, extension)
+}
+
+/// 一篇文章时以文章标题为题, 多篇时以 `title` 为题并带目录, `notes` 为 None 时不附带笔记
+pub fn render(
+    articles: &[Article],
+    title: &str,
+    format: Format,
+    readings: &ReadingOverrides,
+    notes: Option<&Notes>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let none = Notes::default();
+    let notes = notes.unwrap_or(&none);
+    match format {
+        Format::Markdown => Ok(markdown(articles, title, notes).into_bytes()),
+        Format::Html => Ok(html(articles, title, notes).into_bytes()),
+        Format::Epub => epub(articles, title, notes),
+        Format::Latex => Ok(typeset::latex(articles, title, readings, notes).into_bytes()),
+        Format::Typst => Ok(typeset::typst(articles, title, readings, notes).into_bytes()),
+    }
+}
+
+pub fn export<P: AsRef<Path>>(
+    path: P,
+    articles: &[Article],
+    title: &str,
+    format: Format,
+    readings: &ReadingOverrides,
+    notes: Option<&Notes>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(dir) = path.as_ref().parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, render(articles, title, format, readings, notes)?)?;
+    Ok(())
+}
+
+fn markdown(articles: &[Article], title: &str, notes: &Notes) -> String {
+    let mut out = String::new();
+    let level = if articles.len() > 1 {
+        out.push_str(&format!("# {}\n\n", title));
+        for (i, art) in articles.iter().enumerate() {
+            out.push_str(&format!(
+                "{}. [{}](#art-{}) {}\n",
+                i + 1,
+                art.title,
+                i + 1,
+                art.byline()
+            ));
+        }
+        out.push('\n');
+        "##"
+    } else {
+        "#"
+    };
+    for (i, art) in articles.iter().enumerate() {
+        if articles.len() > 1 {
+            out.push_str(&format!("<a id=\"art-{}\"></a>\n\n", i + 1));
+        }
+        out.push_str(&format!(
+            "{} {}\n\n*{}*\n\n",
+            level,
+            art.title,
+            art.byline()
+        ));
+        for line in paragraphs(&art.content) {
+            out.push_str(&format!("{}\n\n", line));
+        }
+        for (name, text) in sections(art) {
+            out.push_str(&format!("{}# {}\n\n", level, name));
+            for line in paragraphs(text) {
+                out.push_str(&format!("{}\n\n", line));
+            }
+        }
+        let lines = note_lines(art, notes);
+        if !lines.is_empty() {
+            out.push_str(&format!("{}# 笔记\n\n", level));
+            for line in lines {
+                out.push_str(&format!("- {}\n", line));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// 转义 HTML 与 XML 中的特殊字符
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 单篇文章的 HTML 片段, 同时满足 XHTML 的要求, 供 EPUB 复用
+fn article_html(art: &Article, heading: u8, notes: &Notes) -> String {
+    let mut out = format!(
+        "<h{h}>{}</h{h}>\n<p class=\"writer\">{}</p>\n<div class=\"content\">\n",
+        escape(&art.title),
+        escape(&art.byline()),
+        h = heading
+    );
+    for line in paragraphs(&art.content) {
+        out.push_str(&format!("<p>{}</p>\n", escape(line)));
+    }
+    out.push_str("</div>\n");
+    for (name, text) in sections(art) {
+        out.push_str(&format!("<h{h}>{}</h{h}>\n", name, h = heading + 1));
+        for line in paragraphs(text) {
+            out.push_str(&format!("<p>{}</p>\n", escape(line)));
+        }
+    }
+    let lines = note_lines(art, notes);
+    if !lines.is_empty() {
+        out.push_str(&format!("<h{h}>笔记</h{h}>\n<ul>\n", h = heading + 1));
+        for line in lines {
+            out.push_str(&format!("<li>{}</li>\n", escape(&line)));
+        }
+        out.push_str("</ul>\n");
+    }
+    out
+}
+
+fn html(articles: &[Article], title: &str, notes: &Notes) -> String {
+    let title = match articles {
+        [art] => art.title.as_str(),
+        _ => title,
+    };
+    let mut body = String::new();
+    if articles.len() > 1 {
+        body.push_str(&format!("<h1>{}</h1>\n<nav>\n<ol>\n", escape(title)));
+        for (i, art) in articles.iter().enumerate() {
+            body.push_str(&format!(
+                "<li><a href=\"#art-{}\">{}</a> {}</li>\n",
+                i + 1,
+                escape(&art.title),
+                escape(&art.byline())
+            ));
+        }
+        body.push_str("</ol>\n</nav>\n");
+        for (i, art) in articles.iter().enumerate() {
+            body.push_str(&format!(
+                "<article id=\"art-{}\">\n{}</article>\n",
+                i + 1,
+                article_html(art, 2, notes)
+            ));
+        }
+    } else {
+        for art in articles {
+            body.push_str(&format!(
+                "<article>\n{}</article>\n",
+                article_html(art, 1, notes)
+            ));
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn xhtml(title: &str, body: &str, extra_ns: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\"{} xml:lang=\"zh-CN\" lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\"/>\n<title>{}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n{}</body>\n</html>\n",
+        extra_ns,
+        escape(title),
+        body
+    )
+}
+
+/// EPUB 3, 每篇文章一章, 附带导航目录
+fn epub(
+    articles: &[Article],
+    title: &str,
+    notes: &Notes,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let title = match articles {
+        [art] => art.title.as_str(),
+        _ => title,
+    };
+    let mut hasher = DefaultHasher::new();
+    for art in articles {
+        art.id.hash(&mut hasher);
+    }
+    let uid = format!("urn:enlighten:{:016x}", hasher.finish());
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    // mimetype 必须是第一个文件且不压缩
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(
+        b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n<rootfiles>\n<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\n</rootfiles>\n</container>\n",
+    )?;
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(STYLE.as_bytes())?;
+
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    let mut toc = String::new();
+    for (i, art) in articles.iter().enumerate() {
+        let name = format!("art-{}.xhtml", i + 1);
+        zip.start_file(format!("OEBPS/{}", name), deflated)?;
+        zip.write_all(xhtml(&art.title, &article_html(art, 1, notes), "").as_bytes())?;
+        manifest.push_str(&format!(
+            "<item id=\"art-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            i + 1,
+            name
+        ));
+        spine.push_str(&format!("<itemref idref=\"art-{}\"/>\n", i + 1));
+        toc.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            name,
+            escape(&art.title)
+        ));
+    }
+    let nav = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>目录</h1>\n<ol>\n{}</ol>\n</nav>\n",
+        toc
+    );
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(xhtml(title, &nav, " xmlns:epub=\"http://www.idpf.org/2007/ops\"").as_bytes())?;
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"uid\" xml:lang=\"zh-CN\">\n<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<dc:identifier id=\"uid\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>zh-CN</dc:language>\n<meta property=\"dcterms:modified\">{}</meta>\n</metadata>\n<manifest>\n<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n<item id=\"css\" href=\"style.css\" media-type=\"text/css\"/>\n{}</manifest>\n<spine>\n{}</spine>\n</package>\n",
+        uid,
+        escape(title),
+        utc_now(),
+        manifest,
+        spine
+    );
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(opf.as_bytes())?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// 当前 UTC 时间, 形如 2021-03-01T08:00:00Z
+fn utc_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    // 按公历由天数推算年月日
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::jingyesi;
+    use std::io::Read;
+
+    fn articles() -> Vec<Article> {
+        vec![
+            Article {
+                translation: Some("明亮的月光洒在窗户纸上。".to_string()),
+                ..jingyesi()
+            },
+            Article {
+                id: "2".to_string(),
+                title: "春晓".to_string(),
+                writer: "孟浩然".to_string(),
+                content: "春眠不觉晓，处处闻啼鸟。".to_string(),
+                shangxi: Some("<b>春</b> & 晓".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_collect_results() {
+        use crate::api::{Method, LOCAL_PAGE_SIZE};
+        let found: Vec<ArticleSimple> = (0..LOCAL_PAGE_SIZE * 1000)
+            .map(|i| ArticleSimple {
+                id: i.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let mut fetched = vec![];
+        let items = collect_results::<()>(3, |page| {
+            fetched.push(page);
+            Ok(ArtListResp::paginate(
+                found.clone(),
+                page,
+                Method::default(),
+            ))
+        })
+        .unwrap();
+        // 达到上限后不再请求后面的页
+        assert_eq!(items.len(), MAX_ARTICLES);
+        assert_eq!(
+            fetched,
+            (3..3 + MAX_ARTICLES / LOCAL_PAGE_SIZE).collect::<Vec<_>>()
+        );
+        assert_eq!(items[0].id, (LOCAL_PAGE_SIZE * 2).to_string());
+
+        let mut fetched = vec![];
+        let items = collect_results::<()>(1, |page| {
+            fetched.push(page);
+            Ok(ArtListResp::paginate(
+                found[..15].to_vec(),
+                page,
+                Method::default(),
+            ))
+        })
+        .unwrap();
+        assert_eq!(items.len(), 15);
+        assert_eq!(fetched, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_markdown_and_html() {
+        let arts = articles();
+        let md = markdown(&arts[..1], "收藏", &Notes::default());
+        assert!(md.starts_with("# 静夜思\n\n*〔唐代〕李白*\n\n床前明月光，疑是地上霜。\n\n"));
+        assert!(md.contains("## 翻译\n\n明亮的月光洒在窗户纸上。"));
+        assert!(!md.contains("注释"));
+
+        let mut notes = Notes::default();
+        notes.add("1", Some(2), "望月 & 思乡");
+        let md = markdown(&arts, "收藏", &notes);
+        assert!(md
+            .starts_with("# 收藏\n\n1. [静夜思](#art-1) 〔唐代〕李白\n2. [春晓](#art-2) 孟浩然\n"));
+        assert!(md.contains("## 春晓"));
+        assert!(md.contains("### 赏析"));
+
+        assert!(md.contains("### 笔记\n\n- 第 3 句「举头望明月」：望月 & 思乡\n"));
+
+        let page = html(&arts, "收藏", &notes);
+        assert!(page.contains("<title>收藏</title>"));
+        assert!(page.contains("<li><a href=\"#art-2\">春晓</a> 孟浩然</li>"));
+        assert!(page.contains("&lt;b&gt;春&lt;/b&gt; &amp; 晓"));
+        assert!(page.contains("<li>第 3 句「举头望明月」：望月 &amp; 思乡</li>"));
+        assert!(html(&arts[..1], "收藏", &Notes::default()).contains("<title>静夜思</title>"));
+        assert_eq!(
+            file_name("检索: 李白", Format::Epub.extension()),
+            "检索__李白.epub"
+        );
+    }
+
+    #[test]
+    fn test_render_notes() {
+        let arts = articles();
+        let mut notes = Notes::default();
+        notes.add("1", None, "李白客居扬州时所作");
+        let readings = ReadingOverrides::default();
+        for format in Format::ALL.iter().filter(|f| **f != Format::Epub) {
+            let with = render(&arts, "收藏", *format, &readings, Some(&notes)).unwrap();
+            let without = render(&arts, "收藏", *format, &readings, None).unwrap();
+            assert!(String::from_utf8(with)
+                .unwrap()
+                .contains("李白客居扬州时所作"));
+            assert!(!String::from_utf8(without).unwrap().contains("笔记"));
+        }
+    }
+
+    #[test]
+    fn test_epub() {
+        let bytes = epub(&articles(), "收藏", &Notes::default()).unwrap();
+        // 未压缩的 mimetype 紧跟第一个本地文件头
+        assert_eq!(&bytes[30..38], b"mimetype");
+        assert_eq!(&bytes[38..58], b"application/epub+zip");
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut opf = String::new();
+        archive
+            .by_name("OEBPS/content.opf")
+            .unwrap()
+            .read_to_string(&mut opf)
+            .unwrap();
+        assert!(opf.contains("<itemref idref=\"art-2\"/>"));
+        assert!(opf.contains("<dc:title>收藏</dc:title>"));
+        let mut nav = String::new();
+        archive
+            .by_name("OEBPS/nav.xhtml")
+            .unwrap()
+            .read_to_string(&mut nav)
+            .unwrap();
+        assert!(nav.contains("<a href=\"art-1.xhtml\">静夜思</a>"));
+        assert!(archive.by_name("OEBPS/art-2.xhtml").is_ok());
+    }
+}
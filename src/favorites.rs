@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api::ArticleSimple;
+use crate::store;
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// 收藏的文章, 按收藏先后排列
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    items: Vec<ArticleSimple>,
+}
+
+impl Favorites {
+    pub fn load() -> Favorites {
+        store::load(FAVORITES_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(FAVORITES_FILE, self)
+    }
+
+    pub fn items(&self) -> &[ArticleSimple] {
+        &self.items
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.items.iter().any(|art| art.id == id)
+    }
+
+    /// 未收藏则加入, 已收藏则移除, 返回操作后是否处于收藏状态
+    pub fn toggle(&mut self, art: &ArticleSimple) -> bool {
+        if self.contains(&art.id) {
+            self.items.retain(|a| a.id != art.id);
+            false
+        } else {
+            self.items.push(ArticleSimple {
+                snippet: None,
+                ..art.clone()
+            });
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle() {
+        let mut favorites = Favorites::default();
+        let art = ArticleSimple {
+            id: "1".to_string(),
+            title: "静夜思".to_string(),
+            snippet: Some("床前明月光".to_string()),
+        };
+        assert!(favorites.toggle(&art));
+        assert!(favorites.contains("1"));
+        assert_eq!(favorites.items()[0].snippet, None);
+        assert!(!favorites.toggle(&art));
+        assert!(favorites.items().is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+use crate::corpus::Corpus;
+use crate::poem::normalize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Solo,
+    HotSeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Player {
+    First,
+    Second,
+}
+
+impl Player {
+    pub fn other(self) -> Player {
+        match self {
+            Player::First => Player::Second,
+            Player::Second => Player::First,
+        }
+    }
+
+    pub fn name(self, mode: Mode) -> &'static str {
+        match (mode, self) {
+            (Mode::Solo, Player::First) => "玩家",
+            (Mode::Solo, Player::Second) => "电脑",
+            (Mode::HotSeat, Player::First) => "甲",
+            (Mode::HotSeat, Player::Second) => "乙",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verse {
+    pub line: String,
+    pub article_id: String,
+    pub title: String,
+    pub writer: String,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Reject {
+    #[error("请输入诗句")]
+    Empty,
+    #[error("句中没有「{0}」字")]
+    MissingKeyword(char),
+    #[error("本地文集中找不到此句")]
+    NotFound,
+    #[error("此句已被使用")]
+    Repeated,
+}
+
+#[derive(Debug)]
+pub struct Game {
+    pub keyword: char,
+    pub mode: Mode,
+    pub turn: Player,
+    pub history: Vec<(Player, Verse)>,
+    verses: HashMap<String, Verse>,
+    used: HashSet<String>,
+}
+
+impl Game {
+    pub fn new(corpus: &Corpus, keyword: char, mode: Mode) -> Game {
+        let mut verses = HashMap::new();
+        for art in corpus.articles() {
+            for line in art.poem().lines() {
+                let line = &line.text;
+                if line.contains(keyword) && !verses.contains_key(line) {
+                    let verse = Verse {
+                        line: line.clone(),
+                        article_id: art.id.clone(),
+                        title: art.title.clone(),
+                        writer: art.writer.clone(),
+                    };
+                    verses.insert(line.clone(), verse);
+                }
+            }
+        }
+        Game {
+            keyword,
+            mode,
+            turn: Player::First,
+            history: vec![],
+            verses,
+            used: HashSet::new(),
+        }
+    }
+
+    /// 文集中含令字的诗句总数
+    pub fn candidates(&self) -> usize {
+        self.verses.len()
+    }
+
+    /// 当前玩家出句, 成功后轮到对方
+    pub fn play(&mut self, input: &str) -> Result<Verse, Reject> {
+        let line = normalize(input);
+        if line.is_empty() {
+            return Err(Reject::Empty);
+        }
+        if !line.contains(self.keyword) {
+            return Err(Reject::MissingKeyword(self.keyword));
+        }
+        if self.used.contains(&line) {
+            return Err(Reject::Repeated);
+        }
+        match self.verses.get(&line) {
+            Some(verse) => {
+                let verse = verse.clone();
+                self.accept(verse.clone());
+                Ok(verse)
+            }
+            None => Err(Reject::NotFound),
+        }
+    }
+
+    /// 电脑从未使用过的诗句中随机出句, 无句可出时返回 None
+    pub fn computer_play<R: Rng>(&mut self, rng: &mut R) -> Option<Verse> {
+        let unused: Vec<&Verse> = self
+            .verses
+            .values()
+            .filter(|v| !self.used.contains(&v.line))
+            .collect();
+        let verse = unused.choose(rng).map(|v| (*v).clone())?;
+        self.accept(verse.clone());
+        Some(verse)
+    }
+
+    fn accept(&mut self, verse: Verse) {
+        self.used.insert(verse.line.clone());
+        self.history.push((self.turn, verse));
+        self.turn = self.turn.other();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Article;
+    use crate::fixtures::JINGYESI;
+
+    fn corpus() -> Corpus {
+        let mut corpus = Corpus::default();
+        for (id, title, content) in [
+            ("1", "静夜思", JINGYESI),
+            (
+                "2",
+                "月下独酌",
+                "花间一壶酒，独酌无相亲。举杯邀明月，对影成三人。",
+            ),
+        ]
+        .iter()
+        {
+            corpus
+                .insert(Article {
+                    id: id.to_string(),
+                    title: title.to_string(),
+                    writer: "李白".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        corpus
+    }
+
+    #[test]
+    fn test_play_rules() {
+        let mut game = Game::new(&corpus(), '月', Mode::HotSeat);
+        assert_eq!(game.candidates(), 3);
+        assert_eq!(game.play(""), Err(Reject::Empty));
+        assert_eq!(game.play("疑是地上霜"), Err(Reject::MissingKeyword('月')));
+        assert_eq!(game.play("明月几时有"), Err(Reject::NotFound));
+        let verse = game.play("床前明月光，").unwrap();
+        assert_eq!(verse.title, "静夜思");
+        assert_eq!(game.turn, Player::Second);
+        assert_eq!(game.play("床前明月光"), Err(Reject::Repeated));
+        assert_eq!(game.play("举杯邀明月").unwrap().title, "月下独酌");
+        assert_eq!(game.turn, Player::First);
+    }
+
+    #[test]
+    fn test_computer_play() {
+        let mut game = Game::new(&corpus(), '月', Mode::Solo);
+        let mut rng = rand::thread_rng();
+        game.play("举头望明月").unwrap();
+        assert!(game.computer_play(&mut rng).is_some());
+        assert!(game.computer_play(&mut rng).is_some());
+        assert!(game.computer_play(&mut rng).is_none());
+        assert_eq!(game.history.len(), 3);
+    }
+}
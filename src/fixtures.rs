@@ -0,0 +1,15 @@
+use crate::api::Article;
+
+/// 《静夜思》正文
+pub const JINGYESI: &str = "床前明月光，疑是地上霜。\n举头望明月，低头思故乡。";
+
+pub fn jingyesi() -> Article {
+    Article {
+        id: "1".to_string(),
+        title: "静夜思".to_string(),
+        writer: "李白".to_string(),
+        dynasty: "唐代".to_string(),
+        content: JINGYESI.to_string(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,326 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Seek, SeekFrom};
+
+use crate::api::{self, Sentence};
+use crate::poem::{Form, Line, Poem};
+use crate::store;
+
+/// 联网获取的名句缓存在数据目录下, 供之后离线使用
+pub const SENTENCES_FILE: &str = "sentences.json";
+/// 短诗的最大句数, 即律诗的长度
+const SHORT_POEM_LINES: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// 输出短诗而不是名句
+    pub poem: bool,
+    pub writer: Option<String>,
+    pub dynasty: Option<String>,
+    /// 不计标点的最大字数
+    pub length: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fortune {
+    pub text: String,
+    pub from: String,
+}
+
+/// 文章缓存中选取名句所需的字段, 跳过注释与赏析等长文本以加快解析
+#[derive(Debug, Deserialize)]
+struct Brief {
+    title: String,
+    #[serde(default)]
+    writer: String,
+    #[serde(default)]
+    dynasty: String,
+    content: String,
+}
+
+enum Source<'a> {
+    /// 文章缓存中一行的起始位置与长度, 选中时才读取解析
+    Article(u64, usize),
+    Sentence(&'a Sentence),
+}
+
+/// 读取缓存的名句
+pub fn load_sentences() -> Vec<Sentence> {
+    store::load(SENTENCES_FILE)
+}
+
+/// 追加缓存名句, 已有的名句会被忽略
+pub fn save_sentences(cached: &mut Vec<Sentence>, fetched: Vec<Sentence>) {
+    for s in fetched {
+        if !cached.iter().any(|c| c.id == s.id) {
+            cached.push(s);
+        }
+    }
+    if let Err(e) = store::save(SENTENCES_FILE, cached) {
+        log::warn!("failed to cache sentences: {}", e);
+    }
+}
+
+/// 朝代比较时忽略 "代" 字, 如 "唐" 与 "唐代"
+fn same_dynasty(a: &str, b: &str) -> bool {
+    a.trim_end_matches('代') == b.trim_end_matches('代')
+}
+
+fn text_len(text: &str) -> usize {
+    text.chars().filter(|&c| crate::poem::is_text(c)).count()
+}
+
+fn with_punct(lines: &[Line]) -> String {
+    lines.iter().map(Line::with_punct).collect()
+}
+
+fn article_from(art: &Brief) -> String {
+    api::source(&art.dynasty, &art.writer, &art.title)
+}
+
+impl Options {
+    fn accepts_article(&self, art: &Brief) -> bool {
+        self.writer.as_ref().is_none_or(|w| &art.writer == w)
+            && self
+                .dynasty
+                .as_ref()
+                .is_none_or(|d| same_dynasty(&art.dynasty, d))
+    }
+
+    /// 缓存的名句只有出处, 无法按朝代筛选
+    fn accepts_sentence(&self, s: &Sentence) -> bool {
+        self.dynasty.is_none()
+            && self
+                .writer
+                .as_ref()
+                .is_none_or(|w| s.from.contains(w.as_str()))
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        self.length.is_none_or(|max| text_len(text) <= max)
+    }
+
+    /// 作者与朝代须原样出现在缓存行中, 用于在解析前快速排除
+    fn needles(&self) -> Vec<regex::bytes::Regex> {
+        self.writer
+            .iter()
+            .map(String::as_str)
+            .chain(self.dynasty.iter().map(|d| d.trim_end_matches('代')))
+            .filter(|n| !n.is_empty())
+            .filter_map(|n| regex::bytes::Regex::new(&regex::escape(n)).ok())
+            .collect()
+    }
+
+    fn render_sentence(&self, s: &Sentence) -> Option<Fortune> {
+        let text = s.name.trim().to_string();
+        self.fits(&text).then(|| Fortune {
+            text,
+            from: s.from.trim().to_string(),
+        })
+    }
+
+    fn render_article<R: Rng>(&self, line: &[u8], rng: &mut R) -> Option<Fortune> {
+        let art: Brief = serde_json::from_slice(line).ok()?;
+        if !self.accepts_article(&art) {
+            return None;
+        }
+        let poem = Poem::parse(&art.title, &art.content);
+        if self.poem {
+            self.short_poem(&art, &poem)
+        } else {
+            self.couplet(&art, &poem, rng)
+        }
+    }
+
+    fn short_poem(&self, art: &Brief, poem: &Poem) -> Option<Fortune> {
+        let count = poem.lines().count();
+        if poem.form == Form::Wen || count == 0 || count > SHORT_POEM_LINES {
+            return None;
+        }
+        let text = poem
+            .couplets()
+            .iter()
+            .map(|c| with_punct(c))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.fits(&text).then(|| Fortune {
+            text,
+            from: article_from(art),
+        })
+    }
+
+    fn couplet<R: Rng>(&self, art: &Brief, poem: &Poem, rng: &mut R) -> Option<Fortune> {
+        let couplets: Vec<String> = poem
+            .couplets()
+            .iter()
+            .map(|c| with_punct(c))
+            .filter(|t| self.fits(t))
+            .collect();
+        couplets.choose(rng).map(|text| Fortune {
+            text: text.clone(),
+            from: article_from(art),
+        })
+    }
+}
+
+/// 从文章缓存 (`articles.jsonl`) 与缓存的名句中随机选取一条, 名句取自文章中的一联
+///
+/// 文章缓存只扫描一遍记录各行位置, 打乱后逐篇读取尝试, 避免整个文集的解析,
+/// 以便在 shell 启动脚本中快速返回
+pub fn pick<A: BufRead + Seek, R: Rng>(
+    mut articles: A,
+    sentences: &[Sentence],
+    opts: &Options,
+    rng: &mut R,
+) -> io::Result<Option<Fortune>> {
+    let needles = opts.needles();
+    let mut sources: Vec<Source> = vec![];
+    let mut line = vec![];
+    let mut offset = 0;
+    loop {
+        line.clear();
+        let n = articles.read_until(b'\n', &mut line)?;
+        if n == 0 {
+            break;
+        }
+        if !line.trim_ascii().is_empty() && needles.iter().all(|needle| needle.is_match(&line)) {
+            sources.push(Source::Article(offset, n));
+        }
+        offset += n as u64;
+    }
+    if !opts.poem {
+        sources.extend(
+            sentences
+                .iter()
+                .filter(|s| opts.accepts_sentence(s))
+                .map(Source::Sentence),
+        );
+    }
+    sources.shuffle(rng);
+    for source in sources {
+        let fortune = match source {
+            Source::Sentence(s) => opts.render_sentence(s),
+            Source::Article(offset, len) => {
+                articles.seek(SeekFrom::Start(offset))?;
+                let mut buf = vec![0; len];
+                articles.read_exact(&mut buf)?;
+                opts.render_article(&buf, rng)
+            }
+        };
+        if fortune.is_some() {
+            return Ok(fortune);
+        }
+    }
+    Ok(None)
+}
+
+/// 终端显示宽度, 全角字符占两列
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+/// 按显示宽度折行, 宽度为 0 时不折行
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for raw in text.lines() {
+        let mut line = String::new();
+        let mut used = 0;
+        for c in raw.chars() {
+            let w = display_width(&c.to_string());
+            if width > 0 && used + w > width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+                used = 0;
+            }
+            line.push(c);
+            used += w;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Article;
+    use crate::fixtures::JINGYESI;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::io::Cursor;
+
+    fn corpus() -> Cursor<String> {
+        let mut lines = vec![];
+        for (id, title, writer, dynasty, content) in [
+            ("1", "静夜思", "李白", "唐代", JINGYESI),
+            (
+                "2",
+                "水调歌头",
+                "苏轼",
+                "宋代",
+                "明月几时有？把酒问青天。不知天上宫阙，今夕是何年。",
+            ),
+        ] {
+            let art = Article {
+                id: id.to_string(),
+                title: title.to_string(),
+                writer: writer.to_string(),
+                dynasty: dynasty.to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            };
+            lines.push(serde_json::to_string(&art).unwrap());
+        }
+        Cursor::new(lines.join("\n"))
+    }
+
+    #[test]
+    fn test_pick() {
+        let sentences = vec![Sentence {
+            id: "s1".to_string(),
+            name: "人生得意须尽欢，莫使金樽空对月。".to_string(),
+            from: "李白《将进酒》".to_string(),
+        }];
+        let mut rng = StdRng::seed_from_u64(7);
+        let opts = Options {
+            dynasty: Some("宋".to_string()),
+            ..Default::default()
+        };
+        let fortune = pick(corpus(), &sentences, &opts, &mut rng)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fortune.from, "〔宋代〕苏轼《水调歌头》");
+
+        let opts = Options {
+            poem: true,
+            writer: Some("李白".to_string()),
+            ..Default::default()
+        };
+        let fortune = pick(corpus(), &sentences, &opts, &mut rng)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fortune.text, JINGYESI);
+
+        // 字数限制排除了所有候选
+        let opts = Options {
+            length: Some(4),
+            ..Default::default()
+        };
+        assert!(pick(corpus(), &sentences, &opts, &mut rng)
+            .unwrap()
+            .is_none());
+        assert!(pick(Cursor::new(""), &[], &Options::default(), &mut rng)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(display_width("明月a"), 5);
+        assert_eq!(
+            wrap("床前明月光，疑是地上霜。", 12),
+            vec!["床前明月光，", "疑是地上霜。"]
+        );
+        assert_eq!(wrap("床前\n明月", 0), vec!["床前", "明月"]);
+    }
+}
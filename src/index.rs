@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::api::{Article, ArticleSimple};
+use crate::corpus::Corpus;
+use crate::poem::is_text;
+
+/// BM25 参数
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+/// 摘要中命中词前后保留的字数
+const SNIPPET_RADIUS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    Content,
+    Translation,
+    Remark,
+    Shangxi,
+}
+
+impl Field {
+    pub const ALL: [Field; 4] = [
+        Field::Content,
+        Field::Translation,
+        Field::Remark,
+        Field::Shangxi,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Content => "正文",
+            Field::Translation => "翻译",
+            Field::Remark => "注释",
+            Field::Shangxi => "赏析",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Field> {
+        match name {
+            "正文" | "正" | "content" => Some(Field::Content),
+            "翻译" | "译文" | "译" | "translation" => Some(Field::Translation),
+            "注释" | "注" | "remark" => Some(Field::Remark),
+            "赏析" | "赏" | "shangxi" => Some(Field::Shangxi),
+            _ => None,
+        }
+    }
+
+    /// 正文命中比译文, 注释与赏析更重要
+    fn weight(self) -> f32 {
+        match self {
+            Field::Content => 2.0,
+            Field::Translation => 1.0,
+            Field::Remark | Field::Shangxi => 0.5,
+        }
+    }
+
+    fn slot(self) -> usize {
+        self as usize
+    }
+
+    fn text(self, art: &Article) -> &str {
+        match self {
+            Field::Content => &art.content,
+            Field::Translation => art.translation.as_deref().unwrap_or_default(),
+            Field::Remark => art.remark.as_deref().unwrap_or_default(),
+            Field::Shangxi => art.shangxi.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+/// 查询中的一个词, 可限定字段, 如 "翻译:月光"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub text: String,
+    pub field: Option<Field>,
+}
+
+/// 按空白切分查询, `字段:词` 形式的词只在该字段中查找
+pub fn parse_query(query: &str) -> Vec<Term> {
+    query
+        .split_whitespace()
+        .filter_map(|word| {
+            let (field, text) = match word.split_once(&[':', '：'][..]) {
+                Some((name, text)) => match Field::parse(name) {
+                    Some(field) => (Some(field), text),
+                    None => (None, word),
+                },
+                None => (None, word),
+            };
+            let text: String = text.chars().filter(|&c| is_text(c)).collect();
+            if text.is_empty() {
+                None
+            } else {
+                Some(Term { text, field })
+            }
+        })
+        .collect()
+}
+
+/// 汉字按单字与相邻二字切分, 连续的字母数字作为一个词并转为小写
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut prev: Option<char> = None;
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            word.push(c.to_ascii_lowercase());
+            prev = None;
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        if !is_text(c) {
+            prev = None;
+            continue;
+        }
+        tokens.push(c.to_string());
+        if let Some(p) = prev {
+            tokens.push(format!("{}{}", p, c));
+        }
+        prev = Some(c);
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// 查询词对应的检索词元: 单字用单字, 多字用二字组
+fn query_tokens(term: &str) -> Vec<String> {
+    let tokens = tokenize(term);
+    if term.chars().count() == 1 {
+        return tokens;
+    }
+    let grams: Vec<String> = tokens
+        .iter()
+        .filter(|t| t.is_ascii() || t.chars().count() == 2)
+        .cloned()
+        .collect();
+    if grams.is_empty() {
+        tokens
+    } else {
+        grams
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Posting {
+    doc: usize,
+    field: Field,
+    tf: u32,
+}
+
+/// 文集的倒排索引
+#[derive(Debug, Default)]
+pub struct Index {
+    ids: Vec<String>,
+    known: HashSet<String>,
+    /// 各文章各字段的词元数
+    lengths: Vec<[usize; 4]>,
+    total_lengths: [usize; 4],
+    postings: HashMap<String, Vec<Posting>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub id: String,
+    pub title: String,
+    pub score: f32,
+    /// 得分最高的字段
+    pub field: Field,
+    pub snippet: String,
+}
+
+impl Hit {
+    pub fn to_simple(&self) -> ArticleSimple {
+        ArticleSimple {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            snippet: Some(format!("[{}] {}", self.field.name(), self.snippet)),
+        }
+    }
+}
+
+impl Index {
+    pub fn build(corpus: &Corpus) -> Index {
+        let mut index = Index::default();
+        for art in corpus.articles() {
+            index.add(art);
+        }
+        index
+    }
+
+    /// 加入一篇文章, 已收录的文章会被忽略
+    pub fn add(&mut self, art: &Article) {
+        if !self.known.insert(art.id.clone()) {
+            return;
+        }
+        let doc = self.ids.len();
+        self.ids.push(art.id.clone());
+        let mut lengths = [0; 4];
+        for field in Field::ALL.iter() {
+            let tokens = tokenize(field.text(art));
+            lengths[field.slot()] = tokens.len();
+            self.total_lengths[field.slot()] += tokens.len();
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            for token in tokens {
+                *counts.entry(token).or_default() += 1;
+            }
+            for (token, tf) in counts {
+                self.postings.entry(token).or_default().push(Posting {
+                    doc,
+                    field: *field,
+                    tf,
+                });
+            }
+        }
+        self.lengths.push(lengths);
+    }
+
+    /// 检索包含全部查询词的文章, 按 BM25 加权得分从高到低排列
+    pub fn search(&self, corpus: &Corpus, terms: &[Term]) -> Vec<Hit> {
+        if terms.is_empty() || self.ids.is_empty() {
+            return vec![];
+        }
+        let n = self.ids.len() as f32;
+        let mut scores: HashMap<usize, [f32; 4]> = HashMap::new();
+        let mut candidates: Option<HashSet<usize>> = None;
+        for term in terms {
+            let mut matched: HashSet<usize> = HashSet::new();
+            let tokens = query_tokens(&term.text);
+            for (i, token) in tokens.iter().enumerate() {
+                let postings: Vec<&Posting> = self
+                    .postings
+                    .get(token)
+                    .map(|ps| {
+                        ps.iter()
+                            .filter(|p| term.field.is_none_or(|f| f == p.field))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let docs: HashSet<usize> = postings.iter().map(|p| p.doc).collect();
+                let df = docs.len() as f32;
+                let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                for p in postings {
+                    let slot = p.field.slot();
+                    let avg = self.total_lengths[slot] as f32 / n;
+                    let len = self.lengths[p.doc][slot] as f32;
+                    let tf = p.tf as f32;
+                    let norm = tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / avg.max(1.0)));
+                    scores.entry(p.doc).or_default()[slot] += p.field.weight() * idf * norm;
+                }
+                matched = if i == 0 {
+                    docs
+                } else {
+                    matched.intersection(&docs).copied().collect()
+                };
+            }
+            candidates = Some(match candidates {
+                None => matched,
+                Some(c) => c.intersection(&matched).copied().collect(),
+            });
+        }
+
+        let mut hits = vec![];
+        for doc in candidates.unwrap_or_default() {
+            let art = match corpus.get(&self.ids[doc]) {
+                Some(art) => art,
+                None => continue,
+            };
+            // 二字组只保证词元都出现, 这里再确认整词确实出现在字段中
+            let located: Vec<(Field, usize)> = match terms
+                .iter()
+                .map(|term| locate(art, term))
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(located) => located,
+                None => continue,
+            };
+            let field_scores = scores[&doc];
+            let (field, offset) = located
+                .iter()
+                .copied()
+                .max_by(|a, b| field_scores[a.0.slot()].total_cmp(&field_scores[b.0.slot()]))
+                .unwrap();
+            hits.push(Hit {
+                id: art.id.clone(),
+                title: art.title.clone(),
+                score: field_scores.iter().sum(),
+                field,
+                snippet: snippet(field.text(art), offset),
+            });
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        hits
+    }
+}
+
+/// 查询词第一次出现的字段及字节位置, 按字段权重顺序查找
+fn locate(art: &Article, term: &Term) -> Option<(Field, usize)> {
+    let lower = term.text.to_lowercase();
+    Field::ALL
+        .iter()
+        .filter(|f| term.field.is_none_or(|field| field == **f))
+        .find_map(|f| f.text(art).to_lowercase().find(&lower).map(|i| (*f, i)))
+}
+
+/// 命中处前后若干字组成的单行摘要
+fn snippet(text: &str, offset: usize) -> String {
+    let offset = (0..=offset)
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0);
+    let before: Vec<char> = text[..offset].chars().rev().take(SNIPPET_RADIUS).collect();
+    let after: String = text[offset..].chars().take(SNIPPET_RADIUS * 2).collect();
+    let mut out: String = before.into_iter().rev().collect();
+    out.push_str(&after);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::jingyesi;
+
+    fn corpus() -> Corpus {
+        let mut corpus = Corpus::default();
+        corpus
+            .insert(Article {
+                translation: Some("明亮的月光洒在窗户纸上，好像地上泛起了一层霜。".to_string()),
+                ..jingyesi()
+            })
+            .unwrap();
+        corpus
+            .insert(Article {
+                id: "2".to_string(),
+                title: "春晓".to_string(),
+                content: "春眠不觉晓，处处闻啼鸟。\n夜来风雨声，花落知多少。".to_string(),
+                translation: Some("春天睡醒不觉天已大亮，到处是鸟儿清脆的叫声。".to_string()),
+                shangxi: Some("诗人抓住春天早晨刚醒时的一瞬间展开描写。".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        corpus
+    }
+
+    #[test]
+    fn test_tokenize_and_query() {
+        assert_eq!(tokenize("明月，光"), vec!["明", "月", "明月", "光"]);
+        assert_eq!(tokenize("Li Bai"), vec!["li", "bai"]);
+        assert_eq!(
+            parse_query("翻译:月光 春天 未知:词"),
+            vec![
+                Term {
+                    text: "月光".to_string(),
+                    field: Some(Field::Translation)
+                },
+                Term {
+                    text: "春天".to_string(),
+                    field: None
+                },
+                Term {
+                    text: "未知词".to_string(),
+                    field: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let corpus = corpus();
+        let index = Index::build(&corpus);
+
+        let hits = index.search(&corpus, &parse_query("明月"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].field, Field::Content);
+
+        // 只出现在译文中
+        let hits = index.search(&corpus, &parse_query("窗户"));
+        assert_eq!(hits[0].id, "1");
+        assert_eq!(hits[0].field, Field::Translation);
+
+        // 只在第二篇的译文与赏析中出现
+        let hits = index.search(&corpus, &parse_query("春天"));
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].snippet.contains("春天"));
+
+        assert!(index.search(&corpus, &parse_query("正文:春天")).is_empty());
+        assert_eq!(index.search(&corpus, &parse_query("霜 春")).len(), 0);
+        assert_eq!(index.search(&corpus, &parse_query("霜 明")).len(), 1);
+        // 二字组都存在但整词不存在
+        assert!(index.search(&corpus, &parse_query("头望明月光")).is_empty());
+    }
+}
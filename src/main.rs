@@ -1,7 +1,45 @@
+mod align;
+mod anki;
 mod api;
+mod cli;
+mod collection;
+mod concordance;
+mod corpus;
+mod discover;
+mod export;
+mod favorites;
+mod feihua;
+/// 各模块测试共用的数据
+#[cfg(test)]
+mod fixtures;
+mod fortune;
+mod index;
+mod note;
+mod poem;
+mod practice;
+mod prosody;
+mod reading;
+mod related;
+mod remark;
+mod script;
+mod search;
+mod store;
+mod suggest;
+mod tag;
+mod theme;
+mod typeset;
 mod ui;
+mod vertical;
+
+use clap::Parser;
 
 fn main() {
+    let cli = cli::Cli::parse();
+    if cli.command.is_some() {
+        // 子命令可能在任意目录下运行, 找不到日志配置时不输出日志
+        let _ = log4rs::init_file("log.yml", Default::default());
+        std::process::exit(cli::run(cli));
+    }
     log4rs::init_file("log.yml", Default::default()).unwrap();
-    ui::render_ui();
+    ui::render_ui(cli.seed);
 }
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::api::Article;
+use crate::poem::{self, Poem};
+use crate::store;
+
+const NOTES_FILE: &str = "notes.json";
+
+/// 一条笔记, 指定句序号时针对该句, 否则针对整篇文章
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub text: String,
+}
+
+impl Note {
+    /// 笔记所指的位置, 如 "第 3 句「举头望明月」"
+    pub fn label(&self, poem: &Poem) -> String {
+        match self.line {
+            Some(index) => match poem.lines().find(|l| l.index == index) {
+                Some(line) => format!("第 {} 句「{}」", index + 1, line.text),
+                None => format!("第 {} 句", index + 1),
+            },
+            None => "全文".to_string(),
+        }
+    }
+}
+
+/// 个人笔记, 以文章 id 为键, 按添加先后排列
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Notes(HashMap<String, Vec<Note>>);
+
+impl Notes {
+    pub fn load() -> Notes {
+        store::load(NOTES_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(NOTES_FILE, self)
+    }
+
+    pub fn get(&self, article_id: &str) -> &[Note] {
+        self.0
+            .get(article_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn add(&mut self, article_id: &str, line: Option<usize>, text: &str) {
+        self.0
+            .entry(article_id.to_string())
+            .or_default()
+            .push(Note {
+                line,
+                text: text.trim().to_string(),
+            });
+    }
+
+    pub fn remove(&mut self, article_id: &str, idx: usize) {
+        if let Some(notes) = self.0.get_mut(article_id) {
+            if idx < notes.len() {
+                notes.remove(idx);
+            }
+            if notes.is_empty() {
+                self.0.remove(article_id);
+            }
+        }
+    }
+
+    /// 包含全部检索词的笔记, 按文章 id 排序, 返回文章 id 与笔记
+    pub fn search(&self, query: &str) -> Vec<(&str, &Note)> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return vec![];
+        }
+        let mut found: Vec<(&str, &Note)> = self
+            .0
+            .iter()
+            .flat_map(|(id, notes)| notes.iter().map(move |n| (id.as_str(), n)))
+            .filter(|(_, n)| {
+                let text = n.text.to_lowercase();
+                terms.iter().all(|t| text.contains(t.as_str()))
+            })
+            .collect();
+        found.sort_by_key(|(id, _)| *id);
+        found
+    }
+
+    /// 正文中标记句笔记的位置: 各句句末标点之后的字节位置, 及该处笔记的序号(从 1 开始)
+    pub fn markers(&self, art: &Article) -> Vec<(usize, Vec<usize>)> {
+        let notes = self.get(&art.id);
+        let spans = poem::line_spans(&art.content);
+        let mut markers: Vec<(usize, Vec<usize>)> = vec![];
+        for (i, note) in notes.iter().enumerate() {
+            let pos = match note.line.and_then(|line| spans.get(line)) {
+                Some(span) => span.end,
+                None => continue,
+            };
+            match markers.iter_mut().find(|(p, _)| *p == pos) {
+                Some((_, numbers)) => numbers.push(i + 1),
+                None => markers.push((pos, vec![i + 1])),
+            }
+        }
+        markers.sort_by_key(|(pos, _)| *pos);
+        markers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::jingyesi;
+
+    #[test]
+    fn test_markers() {
+        let art = jingyesi();
+        let spans = poem::line_spans(&art.content);
+        assert_eq!(spans.len(), art.poem().lines().count());
+        assert_eq!(&art.content[spans[0].clone()], "床前明月光，");
+        assert_eq!(&art.content[spans[2].clone()], "举头望明月，");
+        let content = "曰：“学不可以已。”青，取之于蓝";
+        let spans = poem::line_spans(content);
+        assert_eq!(&content[spans[1].clone()], "学不可以已。”");
+        assert_eq!(
+            spans.iter().map(|s| s.end).collect::<Vec<_>>(),
+            vec![6, 30, 36, 48]
+        );
+
+        let mut notes = Notes::default();
+        notes.add("1", None, "思乡名篇");
+        notes.add("1", Some(2), " 望月 ");
+        notes.add("1", Some(2), "低头");
+        notes.add("1", Some(9), "越界");
+        assert_eq!(notes.get("1")[1].text, "望月");
+        let end = poem::line_spans(&art.content)[2].end;
+        assert_eq!(notes.markers(&art), vec![(end, vec![2, 3])]);
+        assert_eq!(
+            notes.get("1")[1].label(&art.poem()),
+            "第 3 句「举头望明月」"
+        );
+        assert_eq!(notes.get("1")[0].label(&art.poem()), "全文");
+    }
+
+    #[test]
+    fn test_search_and_remove() {
+        let mut notes = Notes::default();
+        notes.add("2", None, "Rhyme 押韵");
+        notes.add("1", Some(0), "押韵 平声");
+        let found = notes.search("押韵");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].0, "1");
+        assert_eq!(notes.search("rhyme 押韵").len(), 1);
+        assert!(notes.search(" ").is_empty());
+        notes.remove("2", 0);
+        assert!(notes.get("2").is_empty());
+        assert_eq!(notes.search("押韵").len(), 1);
+    }
+}
@@ -0,0 +1,353 @@
+use serde::Serialize;
+use std::ops::Range;
+
+use crate::api::Article;
+
+pub const CN_PUNCTIONS: [char; 76] = [
+    '！', '？', '｡', '。', '＂', '＃', '＄', '％', '＆', '＇', '（', '）', '＊', '＋', '，', '－',
+    '／', '：', '；', '＜', '＝', '＞', '＠', '［', '＼', '］', '＾', '＿', '｀', '｛', '｜', '｝',
+    '～', '｟', '｠', '｢', '｣', '､', '、', '〃', '《', '》', '「', '」', '『', '』', '【', '】',
+    '〔', '〕', '〖', '〗', '〘', '〙', '〚', '〛', '〜', '〝', '〞', '〟', '〰', '〾', '〿', '–',
+    '—', '‘', '’', '‛', '“', '”', '„', '‟', '…', '‧', '﹏', '.',
+];
+
+/// 常见词牌, 用于标题中没有 "·" 的词
+const CI_PAI: [&str; 24] = [
+    "如梦令",
+    "长相思",
+    "忆江南",
+    "浣溪沙",
+    "菩萨蛮",
+    "西江月",
+    "清平乐",
+    "卜算子",
+    "蝶恋花",
+    "念奴娇",
+    "水调歌头",
+    "满江红",
+    "虞美人",
+    "破阵子",
+    "鹊桥仙",
+    "渔家傲",
+    "青玉案",
+    "声声慢",
+    "江城子",
+    "永遇乐",
+    "沁园春",
+    "雨霖铃",
+    "临江仙",
+    "一剪梅",
+];
+/// 句读: 诗句在这些标点处断开
+const LINE_BREAKS: [char; 7] = ['，', '。', '！', '？', '；', '：', '、'];
+/// 句末: 一联(或一句话)在这些标点处结束
+const SENTENCE_ENDS: [char; 4] = ['。', '！', '？', '；'];
+/// 句读之后仍归入本句的收尾标点
+const CLOSING: [char; 8] = ['”', '’', '」', '』', '）', '》', '〕', '】'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Form {
+    WuJue,
+    QiJue,
+    WuLv,
+    QiLv,
+    GuTi,
+    Ci,
+    Wen,
+}
+
+impl Form {
+    /// 近体诗: 绝句与律诗
+    pub fn is_regulated(self) -> bool {
+        matches!(self, Form::WuJue | Form::QiJue | Form::WuLv | Form::QiLv)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Form::WuJue => "五言绝句",
+            Form::QiJue => "七言绝句",
+            Form::WuLv => "五言律诗",
+            Form::QiLv => "七言律诗",
+            Form::GuTi => "古体",
+            Form::Ci => "词",
+            Form::Wen => "文",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Line {
+    /// 全文中的句序号, 从 0 开始
+    pub index: usize,
+    /// 去掉标点后的诗句
+    pub text: String,
+    /// 句末标点
+    pub punct: Option<char>,
+}
+
+impl Line {
+    pub fn char_count(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// 诗句连同句末标点
+    pub fn with_punct(&self) -> String {
+        format!(
+            "{}{}",
+            self.text,
+            self.punct.map(String::from).unwrap_or_default()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stanza {
+    pub lines: Vec<Line>,
+}
+
+impl Stanza {
+    /// 按句末标点将诗句分组, 对近体诗而言即为一联
+    pub fn couplets(&self) -> Vec<&[Line]> {
+        let mut couplets = vec![];
+        let mut start = 0;
+        for (i, line) in self.lines.iter().enumerate() {
+            let end = matches!(line.punct, Some(p) if SENTENCE_ENDS.contains(&p));
+            if end || i + 1 == self.lines.len() {
+                couplets.push(&self.lines[start..=i]);
+                start = i + 1;
+            }
+        }
+        couplets
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Poem {
+    pub form: Form,
+    pub stanzas: Vec<Stanza>,
+}
+
+impl Poem {
+    pub fn parse(title: &str, content: &str) -> Poem {
+        let mut paragraphs: Vec<Vec<Line>> = vec![];
+        let mut blank_before = vec![];
+        let mut index = 0;
+        let mut blank = false;
+        for raw in content.lines() {
+            let lines = split_paragraph(raw, &mut index);
+            if lines.is_empty() {
+                blank = !paragraphs.is_empty();
+                continue;
+            }
+            blank_before.push(blank);
+            paragraphs.push(lines);
+            blank = false;
+        }
+
+        let all: Vec<&Line> = paragraphs.iter().flatten().collect();
+        let form = classify(title, &all);
+        let stanzas = match form {
+            Form::Ci | Form::Wen => paragraphs
+                .into_iter()
+                .map(|lines| Stanza { lines })
+                .collect(),
+            _ => {
+                let mut stanzas: Vec<Stanza> = vec![];
+                for (lines, blank) in paragraphs.into_iter().zip(blank_before) {
+                    match stanzas.last_mut() {
+                        Some(stanza) if !blank => stanza.lines.extend(lines),
+                        _ => stanzas.push(Stanza { lines }),
+                    }
+                }
+                stanzas
+            }
+        };
+        Poem { form, stanzas }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.stanzas.iter().flat_map(|s| s.lines.iter())
+    }
+
+    pub fn couplets(&self) -> Vec<&[Line]> {
+        self.stanzas.iter().flat_map(|s| s.couplets()).collect()
+    }
+}
+
+impl Article {
+    /// 解析正文的结构与体裁
+    pub fn poem(&self) -> Poem {
+        Poem::parse(&self.title, &self.content)
+    }
+}
+
+/// 去除空白和标点, 用于诗句的精确比对
+pub fn normalize(line: &str) -> String {
+    line.chars().filter(|c| is_text(*c)).collect()
+}
+
+/// 是否为正文文字, 即非空白非标点
+pub fn is_text(c: char) -> bool {
+    !c.is_whitespace() && !c.is_ascii_punctuation() && !CN_PUNCTIONS.contains(&c)
+}
+
+/// 按 `Poem::parse` 的断句规则, 求各句在正文中的位置:
+/// 从句首文字起, 到句末标点及其后的收尾标点止
+pub fn line_spans(content: &str) -> Vec<Range<usize>> {
+    let mut spans = vec![];
+    let mut start = None;
+    // 当前句已遇到句读, 之后只有后引号等收尾标点仍属于本句
+    let mut broken = false;
+    let mut end = 0;
+    for (i, c) in content.char_indices() {
+        if c == '\n' {
+            if let Some(start) = start.take() {
+                spans.push(start..end);
+            }
+            broken = false;
+        } else if is_text(c) {
+            if broken {
+                spans.extend(start.take().map(|start| start..end));
+                broken = false;
+            }
+            start.get_or_insert(i);
+            end = i + c.len_utf8();
+        } else if start.is_some() && !c.is_whitespace() {
+            if LINE_BREAKS.contains(&c) {
+                broken = true;
+                end = i + c.len_utf8();
+            } else if !broken || CLOSING.contains(&c) {
+                end = i + c.len_utf8();
+            }
+        }
+    }
+    spans.extend(start.map(|start| start..end));
+    spans
+}
+
+fn split_paragraph(raw: &str, index: &mut usize) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for c in raw.chars() {
+        if LINE_BREAKS.contains(&c) {
+            push_line(&mut lines, &mut current, Some(c), index);
+        } else {
+            current.push(c);
+        }
+    }
+    push_line(&mut lines, &mut current, None, index);
+    lines
+}
+
+fn push_line(lines: &mut Vec<Line>, current: &mut String, punct: Option<char>, index: &mut usize) {
+    let text = normalize(current);
+    current.clear();
+    if text.is_empty() {
+        // 连续标点(如 "？！")归入上一句
+        if let (Some(p), Some(last)) = (punct, lines.last_mut()) {
+            if !matches!(last.punct, Some(lp) if SENTENCE_ENDS.contains(&lp)) {
+                last.punct = Some(p);
+            }
+        }
+        return;
+    }
+    lines.push(Line {
+        index: *index,
+        text,
+        punct,
+    });
+    *index += 1;
+}
+
+fn classify(title: &str, lines: &[&Line]) -> Form {
+    if lines.is_empty() {
+        return Form::Wen;
+    }
+    if title.contains('·') || title.contains('・') || CI_PAI.iter().any(|p| title.starts_with(p))
+    {
+        return Form::Ci;
+    }
+    let first = lines[0].char_count();
+    if lines.iter().all(|l| l.char_count() == first) && (first == 5 || first == 7) {
+        return match (first, lines.len()) {
+            (5, 4) => Form::WuJue,
+            (7, 4) => Form::QiJue,
+            (5, 8) => Form::WuLv,
+            (7, 8) => Form::QiLv,
+            _ => Form::GuTi,
+        };
+    }
+    // 古体诗句式大体整齐, 散文则长短错落
+    let mut counts = std::collections::HashMap::new();
+    for line in lines {
+        *counts.entry(line.char_count()).or_insert(0) += 1;
+    }
+    let (mode, count) = counts.into_iter().max_by_key(|&(_, c)| c).unwrap();
+    if (3..=9).contains(&mode) && count as f32 >= lines.len() as f32 * 0.6 {
+        Form::GuTi
+    } else {
+        Form::Wen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::JINGYESI;
+
+    #[test]
+    fn test_parse_jueju() {
+        let poem = Poem::parse("静夜思", JINGYESI);
+        assert_eq!(poem.form, Form::WuJue);
+        assert_eq!(poem.stanzas.len(), 1);
+        let lines: Vec<&str> = poem.lines().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            lines,
+            vec!["床前明月光", "疑是地上霜", "举头望明月", "低头思故乡"]
+        );
+        let couplets = poem.couplets();
+        assert_eq!(couplets.len(), 2);
+        assert_eq!(couplets[1][0].index, 2);
+        assert_eq!(couplets[1][1].punct, Some('。'));
+    }
+
+    #[test]
+    fn test_parse_lvshi() {
+        let poem = Poem::parse(
+            "登高",
+            "风急天高猿啸哀，渚清沙白鸟飞回。无边落木萧萧下，不尽长江滚滚来。\
+             万里悲秋常作客，百年多病独登台。艰难苦恨繁霜鬓，潦倒新停浊酒杯。",
+        );
+        assert_eq!(poem.form, Form::QiLv);
+        assert_eq!(poem.couplets().len(), 4);
+    }
+
+    #[test]
+    fn test_parse_ci_stanzas() {
+        let poem = Poem::parse(
+            "如梦令·昨夜雨疏风骤",
+            "昨夜雨疏风骤，浓睡不消残酒。\n试问卷帘人，却道海棠依旧。知否，知否？应是绿肥红瘦。",
+        );
+        assert_eq!(poem.form, Form::Ci);
+        assert_eq!(poem.stanzas.len(), 2);
+        assert_eq!(poem.stanzas[1].lines[3].text, "知否");
+    }
+
+    #[test]
+    fn test_parse_guti_and_wen() {
+        let poem = Poem::parse(
+            "将进酒",
+            "君不见黄河之水天上来，奔流到海不复回。君不见高堂明镜悲白发，朝如青丝暮成雪。\
+             人生得意须尽欢，莫使金樽空对月。天生我材必有用，千金散尽还复来。\
+             烹羊宰牛且为乐，会须一饮三百杯。",
+        );
+        assert_eq!(poem.form, Form::GuTi);
+        let poem = Poem::parse(
+            "岳阳楼记",
+            "庆历四年春，滕子京谪守巴陵郡。越明年，政通人和，百废具兴，乃重修岳阳楼，\
+             增其旧制，刻唐贤今人诗赋于其上，属予作文以记之。\n予观夫巴陵胜状，在洞庭一湖。",
+        );
+        assert_eq!(poem.form, Form::Wen);
+        assert_eq!(poem.stanzas.len(), 2);
+    }
+}
@@ -0,0 +1,320 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::export::{self, escape};
+use crate::poem;
+use crate::reading::Annotated;
+
+/// 字帖为 A4 纸, 以毫米为单位
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN_TOP: f32 = 25.0;
+const MARGIN_BOTTOM: f32 = 15.0;
+const CELL: f32 = 17.0;
+const PINYIN_HEIGHT: f32 = 8.0;
+/// 每行格数
+pub const COLUMNS: usize = 10;
+
+const GRID_COLOR: &str = "#c0392b";
+const TRACE_COLOR: &str = "#c8c8c8";
+const FONT: &str = "KaiTi, STKaiti, 'AR PL UKai CN', serif";
+
+/// 格子样式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grid {
+    /// 田字格
+    Tian,
+    /// 米字格
+    Mi,
+}
+
+impl Grid {
+    pub const ALL: [Grid; 2] = [Grid::Tian, Grid::Mi];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Grid::Tian => "田字格",
+            Grid::Mi => "米字格",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub grid: Grid,
+    /// 范字之后的格子写上浅色字供描红, 否则留空
+    pub trace: bool,
+    /// 每行格子上方加拼音格
+    pub pinyin: bool,
+    /// 每个字在范字之后的练习次数
+    pub repeat: usize,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            grid: Grid::Tian,
+            trace: true,
+            pinyin: true,
+            repeat: COLUMNS - 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellKind {
+    /// 范字
+    Model,
+    /// 描红字
+    Trace,
+    Blank,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub reading: Option<String>,
+    pub kind: CellKind,
+}
+
+/// 逐字排格: 每个字另起一行, 先写范字, 再接 `repeat` 个练习格, 超出一行时折行, 标点不入格
+pub fn layout(annotated: &[Annotated], opts: &Options) -> Vec<Vec<Cell>> {
+    let mut rows = vec![];
+    for a in annotated.iter().filter(|a| poem::is_text(a.ch)) {
+        let practice = if opts.trace {
+            CellKind::Trace
+        } else {
+            CellKind::Blank
+        };
+        let cells: Vec<Cell> = std::iter::once(CellKind::Model)
+            .chain(std::iter::repeat_n(practice, opts.repeat))
+            .map(|kind| Cell {
+                ch: a.ch,
+                reading: a.reading.clone(),
+                kind,
+            })
+            .collect();
+        rows.extend(cells.chunks(COLUMNS).map(|row| row.to_vec()));
+    }
+    rows
+}
+
+fn row_height(opts: &Options) -> f32 {
+    if opts.pinyin {
+        CELL + PINYIN_HEIGHT
+    } else {
+        CELL
+    }
+}
+
+/// 每页可容纳的行数
+pub fn rows_per_page(opts: &Options) -> usize {
+    ((PAGE_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM) / row_height(opts)) as usize
+}
+
+fn line(out: &mut String, x1: f32, y1: f32, x2: f32, y2: f32, dashed: bool) {
+    let dash = if dashed {
+        r#" stroke-dasharray="1,1" stroke-width="0.2""#
+    } else {
+        r#" stroke-width="0.4""#
+    };
+    out.push_str(&format!(
+        r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}"{}/>"#,
+        x1, y1, x2, y2, dash
+    ));
+    out.push('\n');
+}
+
+/// 四线三格, 中间两线为虚线
+fn pinyin_lines(out: &mut String, x: f32, y: f32, width: f32) {
+    for i in 0..4 {
+        let ly = y + PINYIN_HEIGHT * i as f32 / 3.0;
+        line(out, x, ly, x + width, ly, i == 1 || i == 2);
+    }
+}
+
+fn grid_cell(out: &mut String, x: f32, y: f32, grid: Grid) {
+    out.push_str(&format!(
+        r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="none" stroke-width="0.4"/>"#,
+        x, y, CELL, CELL
+    ));
+    out.push('\n');
+    let half = CELL / 2.0;
+    line(out, x, y + half, x + CELL, y + half, true);
+    line(out, x + half, y, x + half, y + CELL, true);
+    if grid == Grid::Mi {
+        line(out, x, y, x + CELL, y + CELL, true);
+        line(out, x + CELL, y, x, y + CELL, true);
+    }
+}
+
+fn text(out: &mut String, x: f32, y: f32, size: f32, color: &str, content: &str) {
+    out.push_str(&format!(
+        r#"<text x="{:.1}" y="{:.1}" font-size="{:.1}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+        x,
+        y,
+        size,
+        color,
+        escape(content)
+    ));
+    out.push('\n');
+}
+
+fn page(title: &str, rows: &[Vec<Cell>], opts: &Options, number: usize, total: usize) -> String {
+    let mut out = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}" font-family="{font}">
+<rect width="{w}" height="{h}" fill="white"/>
+"#,
+        w = PAGE_WIDTH,
+        h = PAGE_HEIGHT,
+        font = FONT
+    );
+    text(
+        &mut out,
+        PAGE_WIDTH / 2.0,
+        MARGIN_TOP / 2.0,
+        6.0,
+        "black",
+        title,
+    );
+    let left = (PAGE_WIDTH - CELL * COLUMNS as f32) / 2.0;
+    out.push_str(&format!(r#"<g stroke="{}">"#, GRID_COLOR));
+    out.push('\n');
+    for (i, _) in rows.iter().enumerate() {
+        let y = MARGIN_TOP + row_height(opts) * i as f32;
+        if opts.pinyin {
+            pinyin_lines(&mut out, left, y, CELL * COLUMNS as f32);
+        }
+        let cell_y = if opts.pinyin { y + PINYIN_HEIGHT } else { y };
+        for col in 0..COLUMNS {
+            grid_cell(&mut out, left + CELL * col as f32, cell_y, opts.grid);
+        }
+    }
+    out.push_str("</g>\n");
+    for (i, row) in rows.iter().enumerate() {
+        let y = MARGIN_TOP + row_height(opts) * i as f32;
+        let cell_y = if opts.pinyin { y + PINYIN_HEIGHT } else { y };
+        for (col, cell) in row.iter().enumerate() {
+            let color = match cell.kind {
+                CellKind::Model => "black",
+                CellKind::Trace => TRACE_COLOR,
+                CellKind::Blank => continue,
+            };
+            let x = left + CELL * col as f32 + CELL / 2.0;
+            if let (true, Some(reading)) = (opts.pinyin, &cell.reading) {
+                text(&mut out, x, y + PINYIN_HEIGHT / 2.0, 4.0, color, reading);
+            }
+            text(
+                &mut out,
+                x,
+                cell_y + CELL / 2.0,
+                CELL * 0.8,
+                color,
+                &cell.ch.to_string(),
+            );
+        }
+    }
+    text(
+        &mut out,
+        PAGE_WIDTH / 2.0,
+        PAGE_HEIGHT - MARGIN_BOTTOM / 2.0,
+        3.5,
+        "gray",
+        &format!("{} / {}", number, total),
+    );
+    out.push_str("</svg>\n");
+    out
+}
+
+/// 生成 A4 字帖, 每页一个 SVG 文档
+pub fn render(title: &str, annotated: &[Annotated], opts: &Options) -> Vec<String> {
+    let rows = layout(annotated, opts);
+    let pages: Vec<&[Vec<Cell>]> = rows.chunks(rows_per_page(opts).max(1)).collect();
+    pages
+        .iter()
+        .enumerate()
+        .map(|(i, rows)| page(title, rows, opts, i + 1, pages.len()))
+        .collect()
+}
+
+/// 将各页写入目录, 多页时文件名加页码, 返回写入的文件
+pub fn save(dir: &Path, title: &str, pages: &[String]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut paths = vec![];
+    for (i, page) in pages.iter().enumerate() {
+        let name = if pages.len() == 1 {
+            title.to_string()
+        } else {
+            format!("{}-{}", title, i + 1)
+        };
+        let path = dir.join(export::file_name(&name, "svg"));
+        fs::write(&path, page)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reading;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_layout() {
+        let annotated = reading::annotate("床前，明月", &HashMap::new());
+        let opts = Options {
+            repeat: 12,
+            ..Default::default()
+        };
+        let rows = layout(&annotated, &opts);
+        // 每字 13 格, 折成 10 + 3 两行, 标点跳过
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[0].len(), COLUMNS);
+        assert_eq!(rows[1].len(), 3);
+        assert_eq!(rows[0][0].kind, CellKind::Model);
+        assert_eq!(rows[0][1].kind, CellKind::Trace);
+        assert_eq!(rows[2][0].ch, '前');
+        assert_eq!(rows[2][0].reading.as_deref(), Some("qián"));
+
+        let opts = Options {
+            trace: false,
+            repeat: 2,
+            ..Default::default()
+        };
+        let rows = layout(&annotated, &opts);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[3][2].kind, CellKind::Blank);
+    }
+
+    #[test]
+    fn test_render() {
+        let annotated = reading::annotate("床前明月光", &HashMap::new());
+        let opts = Options {
+            grid: Grid::Mi,
+            repeat: 29,
+            ..Default::default()
+        };
+        let pages = render("静夜思 <一>", &annotated, &opts);
+        // 每字 3 行, 共 15 行, 带拼音时每页 10 行
+        assert_eq!(rows_per_page(&opts), 10);
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].contains("静夜思 &lt;一&gt;"));
+        assert!(pages[0].contains(">chuáng</text>"));
+        assert!(pages[1].contains(">2 / 2</text>"));
+        // 米字格每格 4 条虚线
+        let dashed = pages[1].matches("stroke-dasharray").count();
+        assert_eq!(dashed, 5 * (COLUMNS * 4 + 2));
+
+        let opts = Options {
+            pinyin: false,
+            ..opts
+        };
+        assert_eq!(rows_per_page(&opts), 15);
+        let pages = render("静夜思", &annotated, &opts);
+        assert_eq!(pages.len(), 1);
+        assert!(!pages[0].contains("chuáng"));
+    }
+}
@@ -0,0 +1,398 @@
+use pinyin::ToPinyin;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+use crate::poem::{self, Form, Poem};
+
+const PINGSHUI: &str = include_str!("../assets/pingshui.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tone {
+    Ping,
+    Ze,
+    /// 可平可仄
+    Either,
+}
+
+impl Tone {
+    pub fn symbol(self) -> char {
+        match self {
+            Tone::Ping => '平',
+            Tone::Ze => '仄',
+            Tone::Either => '中',
+        }
+    }
+
+    /// 两个声调确定相同
+    fn same(self, other: Tone) -> bool {
+        self != Tone::Either && self == other
+    }
+
+    /// 两个声调确定相反
+    fn differ(self, other: Tone) -> bool {
+        self != Tone::Either && other != Tone::Either && self != other
+    }
+}
+
+/// 平水韵韵部表
+#[derive(Debug)]
+pub struct RhymeTable {
+    groups: Vec<String>,
+    chars: HashMap<char, Vec<usize>>,
+    both: HashSet<char>,
+}
+
+impl RhymeTable {
+    pub fn bundled() -> RhymeTable {
+        RhymeTable::parse(PINGSHUI)
+    }
+
+    pub fn parse(text: &str) -> RhymeTable {
+        let mut table = RhymeTable {
+            groups: vec![],
+            chars: HashMap::new(),
+            both: HashSet::new(),
+        };
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let mut parts = line.split_whitespace();
+            let (name, chars) = match (parts.next(), parts.next()) {
+                (Some(name), Some(chars)) => (name, chars),
+                _ => continue,
+            };
+            if name == "两读" {
+                table.both.extend(chars.chars());
+                continue;
+            }
+            let idx = table.groups.len();
+            table.groups.push(name.to_string());
+            for c in chars.chars() {
+                table.chars.entry(c).or_default().push(idx);
+            }
+        }
+        table
+    }
+
+    /// 按名称查找韵部, 可只写韵目, 如 "尤" 或 "十一尤"
+    pub fn find_group(&self, name: &str) -> Result<&str, QueryError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(QueryError::UnknownGroup(name.to_string()));
+        }
+        if let Some(group) = self.groups.iter().find(|g| *g == name) {
+            return Ok(group);
+        }
+        let found: Vec<&String> = self.groups.iter().filter(|g| g.ends_with(name)).collect();
+        match found.as_slice() {
+            [group] => Ok(group.as_str()),
+            _ => Err(QueryError::UnknownGroup(name.to_string())),
+        }
+    }
+
+    /// 字所属的韵部, 平声韵部在前
+    pub fn groups_of(&self, c: char) -> Vec<&str> {
+        self.chars
+            .get(&c)
+            .map(|ids| ids.iter().map(|&i| self.groups[i].as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn tone(&self, c: char) -> Tone {
+        if self.both.contains(&c) {
+            return Tone::Either;
+        }
+        if let Some(ids) = self.chars.get(&c) {
+            let ping = ids.iter().any(|&i| !self.groups[i].starts_with("入声"));
+            let ze = ids.iter().any(|&i| self.groups[i].starts_with("入声"));
+            return match (ping, ze) {
+                (true, false) => Tone::Ping,
+                (false, true) => Tone::Ze,
+                _ => Tone::Either,
+            };
+        }
+        // 表中未收的字按今音推断: 阴平阳平为平, 上声去声为仄
+        match c.to_pinyin().map(|p| p.with_tone_num_end()) {
+            Some(p) if p.ends_with('1') || p.ends_with('2') => Tone::Ping,
+            Some(p) if p.ends_with('3') || p.ends_with('4') => Tone::Ze,
+            _ => Tone::Either,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("未知韵部: {0}")]
+    UnknownGroup(String),
+    #[error("平仄格式应由 平/仄/中 组成, 句间以标点分隔: {0}")]
+    InvalidPattern(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTones {
+    pub text: String,
+    pub tones: Vec<Tone>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// 句序号, 从 0 开始
+    pub line: usize,
+    /// 字在句中的位置, 从 0 开始
+    pub pos: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub form: Form,
+    pub lines: Vec<LineTones>,
+    /// 韵脚所押的韵部
+    pub rhyme: Option<String>,
+    pub violations: Vec<Violation>,
+}
+
+/// 分析近体诗的平仄与用韵, 其他体裁返回 None
+pub fn analyze(poem: &Poem, table: &RhymeTable) -> Option<Analysis> {
+    if !poem.form.is_regulated() {
+        return None;
+    }
+    let lines: Vec<LineTones> = poem
+        .lines()
+        .map(|l| LineTones {
+            text: l.text.clone(),
+            tones: l.text.chars().map(|c| table.tone(c)).collect(),
+        })
+        .collect();
+    let mut violations = vec![];
+    let mut violate = |line: usize, pos: Option<usize>, message: String| {
+        violations.push(Violation { line, pos, message })
+    };
+
+    let size = lines[0].tones.len();
+    let keys: &[usize] = if size == 5 { &[1, 3] } else { &[1, 3, 5] };
+    for (i, line) in lines.iter().enumerate() {
+        // 二四(六)分明
+        for pair in keys.windows(2) {
+            if line.tones[pair[0]].same(line.tones[pair[1]]) {
+                violate(
+                    i,
+                    Some(pair[1]),
+                    format!("第{}字应与第{}字平仄相反", pair[1] + 1, pair[0] + 1),
+                );
+            }
+        }
+        let last = *line.tones.last().unwrap();
+        if i % 2 == 1 {
+            if last == Tone::Ze {
+                violate(i, Some(size - 1), "韵脚应为平声".to_string());
+            }
+            if line.tones[size - 3..].iter().all(|&t| t == Tone::Ping) {
+                violate(i, Some(size - 3), "三平尾".to_string());
+            }
+            let ping = line.tones[..size - 1]
+                .iter()
+                .filter(|&&t| t != Tone::Ze)
+                .count();
+            if ping == 1 {
+                violate(i, None, "犯孤平".to_string());
+            }
+        } else if i > 0 && last == Tone::Ping {
+            violate(i, Some(size - 1), "句脚应为仄声".to_string());
+        }
+        // 对: 一联之中出句与对句相反
+        if i % 2 == 1 && lines[i - 1].tones[1].same(line.tones[1]) {
+            violate(i, Some(1), "失对".to_string());
+        }
+        // 粘: 后联出句与前联对句相同
+        if i % 2 == 0 && i > 0 && lines[i - 1].tones[1].differ(line.tones[1]) {
+            violate(i, Some(1), "失粘".to_string());
+        }
+    }
+
+    // 韵脚: 偶数句必押, 首句可押可不押
+    let mut rhyme_lines: Vec<usize> = (1..lines.len()).step_by(2).collect();
+    if lines[0].tones[size - 1] != Tone::Ze {
+        rhyme_lines.insert(0, 0);
+    }
+    // 按出现先后统计, 票数相同时取先出现的韵部
+    let mut counts: Vec<(&str, usize)> = vec![];
+    for &i in rhyme_lines.iter() {
+        let c = lines[i].text.chars().last().unwrap();
+        for group in table.groups_of(c) {
+            if group.starts_with("入声") {
+                continue;
+            }
+            match counts.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((group, 1)),
+            }
+        }
+    }
+    let rhyme = counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(g, _)| g.to_string());
+    if let Some(rhyme) = &rhyme {
+        for &i in rhyme_lines.iter().filter(|&&i| i > 0) {
+            let c = lines[i].text.chars().last().unwrap();
+            let groups = table.groups_of(c);
+            if !groups.is_empty() && !groups.contains(&rhyme.as_str()) {
+                violate(i, Some(size - 1), format!("「{}」不在{}, 出韵", c, rhyme));
+            }
+        }
+    }
+
+    Some(Analysis {
+        form: poem.form,
+        lines,
+        rhyme,
+        violations,
+    })
+}
+
+/// 解析平仄格式, 如 "中仄平平仄，平平仄仄平", 中或 * 表示可平可仄
+pub fn parse_pattern(pattern: &str) -> Result<Vec<Vec<Tone>>, QueryError> {
+    let mut lines = vec![];
+    for part in pattern
+        .split(|c: char| !poem::is_text(c) && c != '*')
+        .filter(|p| !p.is_empty())
+    {
+        let tones = part
+            .chars()
+            .map(|c| match c {
+                '平' => Ok(Tone::Ping),
+                '仄' => Ok(Tone::Ze),
+                '中' | '*' => Ok(Tone::Either),
+                _ => Err(QueryError::InvalidPattern(pattern.to_string())),
+            })
+            .collect::<Result<Vec<Tone>, QueryError>>()?;
+        lines.push(tones);
+    }
+    if lines.is_empty() {
+        return Err(QueryError::InvalidPattern(pattern.to_string()));
+    }
+    Ok(lines)
+}
+
+/// 偶数句句脚均属于指定韵部
+pub fn rhymes_with(poem: &Poem, table: &RhymeTable, group: &str) -> bool {
+    if poem.form == Form::Wen {
+        return false;
+    }
+    let mut endings = poem
+        .lines()
+        .skip(1)
+        .step_by(2)
+        .filter_map(|l| l.text.chars().last())
+        .peekable();
+    endings.peek().is_some() && endings.all(|c| table.groups_of(c).contains(&group))
+}
+
+/// 存在连续若干句与平仄格式逐句吻合
+pub fn matches_pattern(poem: &Poem, table: &RhymeTable, pattern: &[Vec<Tone>]) -> bool {
+    let tones: Vec<Vec<Tone>> = poem
+        .lines()
+        .map(|l| l.text.chars().map(|c| table.tone(c)).collect())
+        .collect();
+    tones.windows(pattern.len()).any(|window| {
+        window.iter().zip(pattern).all(|(line, expect)| {
+            line.len() == expect.len() && line.iter().zip(expect).all(|(&t, &e)| !t.differ(e))
+        })
+    })
+}
+
+/// 文字格式的格律报告
+pub fn report(analysis: &Analysis) -> String {
+    let mut out = format!("体裁: {}\n", analysis.form.name());
+    out.push_str(&format!(
+        "韵部: {}\n\n",
+        analysis.rhyme.as_deref().unwrap_or("未知")
+    ));
+    for line in analysis.lines.iter() {
+        let tones: String = line.tones.iter().map(|t| t.symbol()).collect();
+        out.push_str(&format!("{}  {}\n", line.text, tones));
+    }
+    out.push('\n');
+    if analysis.violations.is_empty() {
+        out.push_str("合律\n");
+    } else {
+        for v in analysis.violations.iter() {
+            out.push_str(&format!("第{}句: {}\n", v.line + 1, v.message));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone() {
+        let table = RhymeTable::bundled();
+        assert_eq!(table.tone('东'), Tone::Ping);
+        // 入声字今读阳平
+        assert_eq!(table.tone('白'), Tone::Ze);
+        assert_eq!(table.tone('看'), Tone::Either);
+        // 表中未收, 按今音
+        assert_eq!(table.tone('去'), Tone::Ze);
+        assert_eq!(table.groups_of('光'), vec!["下平七阳"]);
+    }
+
+    #[test]
+    fn test_analyze_regulated() {
+        let table = RhymeTable::bundled();
+        let poem = Poem::parse(
+            "登鹳雀楼",
+            "白日依山尽，黄河入海流。欲穷千里目，更上一层楼。",
+        );
+        let analysis = analyze(&poem, &table).unwrap();
+        assert_eq!(analysis.rhyme.as_deref(), Some("下平十一尤"));
+        assert!(analysis.violations.is_empty(), "{:?}", analysis.violations);
+        assert_eq!(
+            analysis.lines[0]
+                .tones
+                .iter()
+                .map(|t| t.symbol())
+                .collect::<String>(),
+            "仄仄平平仄"
+        );
+    }
+
+    #[test]
+    fn test_analyze_violations() {
+        let table = RhymeTable::bundled();
+        let poem = Poem::parse("测试", "白日依山尽，黄河入海流。欲穷千里目，更上一层天。");
+        let analysis = analyze(&poem, &table).unwrap();
+        assert!(analysis
+            .violations
+            .iter()
+            .any(|v| v.line == 3 && v.message.contains("出韵")));
+        assert!(analyze(&Poem::parse("将进酒", "君不见黄河之水天上来"), &table).is_none());
+    }
+
+    #[test]
+    fn test_search_by_rhyme_and_pattern() {
+        let table = RhymeTable::bundled();
+        let poem = Poem::parse(
+            "登鹳雀楼",
+            "白日依山尽，黄河入海流。欲穷千里目，更上一层楼。",
+        );
+        let group = table.find_group("尤").unwrap();
+        assert_eq!(group, "下平十一尤");
+        assert!(rhymes_with(&poem, &table, group));
+        assert!(!rhymes_with(
+            &poem,
+            &table,
+            table.find_group("七阳").unwrap()
+        ));
+        assert!(table.find_group("无此韵").is_err());
+
+        let pattern = parse_pattern("中仄平平仄，平平仄仄平").unwrap();
+        assert_eq!(pattern.len(), 2);
+        assert!(matches_pattern(&poem, &table, &pattern));
+        let pattern = parse_pattern("平平平仄仄").unwrap();
+        assert!(!matches_pattern(&poem, &table, &pattern));
+        assert!(parse_pattern("平平上去").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use pinyin::ToPinyin;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::store;
+
+const POLYPHONES: &str = include_str!("../assets/polyphone.txt");
+const OVERRIDES_FILE: &str = "readings.json";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PinyinMode {
+    Off,
+    Above,
+    After,
+}
+
+/// 正文中的一个字及其读音, 非汉字没有读音
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotated {
+    pub ch: char,
+    pub reading: Option<String>,
+}
+
+/// 用户为各篇文章指定的读音, 以文章 id 和字在正文中的下标为键
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReadingOverrides(HashMap<String, HashMap<usize, String>>);
+
+impl ReadingOverrides {
+    pub fn load() -> ReadingOverrides {
+        store::load(OVERRIDES_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(OVERRIDES_FILE, self)
+    }
+
+    pub fn get(&self, article_id: &str) -> HashMap<usize, String> {
+        self.0.get(article_id).cloned().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, article_id: &str, index: usize, reading: &str) {
+        self.0
+            .entry(article_id.to_string())
+            .or_default()
+            .insert(index, reading.to_string());
+    }
+}
+
+type Polyphones = Vec<(Vec<char>, Vec<&'static str>)>;
+
+/// 多音字词表, 按词长从长到短排列, 首次使用时解析
+fn polyphones() -> &'static Polyphones {
+    static PHRASES: OnceLock<Polyphones> = OnceLock::new();
+    PHRASES.get_or_init(parse_polyphones)
+}
+
+fn parse_polyphones() -> Polyphones {
+    let mut phrases: Vec<(Vec<char>, Vec<&'static str>)> = POLYPHONES
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let word: Vec<char> = parts.next()?.chars().collect();
+            let readings: Vec<&str> = parts.collect();
+            if word.len() == readings.len() {
+                Some((word, readings))
+            } else {
+                log::warn!("invalid polyphone entry: {}", l);
+                None
+            }
+        })
+        .collect();
+    phrases.sort_by_key(|(word, _)| std::cmp::Reverse(word.len()));
+    phrases
+}
+
+/// 为文本逐字注音: 优先使用用户指定读音, 其次是多音字词表中最长的匹配, 最后是字的默认读音
+pub fn annotate(text: &str, overrides: &HashMap<usize, String>) -> Vec<Annotated> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut readings: Vec<Option<String>> = chars
+        .iter()
+        .map(|c| c.to_pinyin().map(|p| p.with_tone().to_string()))
+        .collect();
+    let phrases = polyphones();
+    let mut i = 0;
+    while i < chars.len() {
+        let matched = phrases
+            .iter()
+            .find(|(word, _)| chars[i..].starts_with(word));
+        match matched {
+            Some((word, phrase_readings)) => {
+                for (j, r) in phrase_readings.iter().enumerate() {
+                    readings[i + j] = Some(r.to_string());
+                }
+                i += word.len();
+            }
+            None => i += 1,
+        }
+    }
+    for (idx, reading) in overrides.iter() {
+        if let Some(slot) = readings.get_mut(*idx) {
+            *slot = Some(reading.clone());
+        }
+    }
+    chars
+        .into_iter()
+        .zip(readings)
+        .map(|(ch, reading)| Annotated { ch, reading })
+        .collect()
+}
+
+/// 读音置于字后, 如 "床(chuáng)前(qián)"
+pub fn render_after(annotated: &[Annotated]) -> String {
+    annotated
+        .iter()
+        .map(|a| match &a.reading {
+            Some(r) => format!("{}({})", a.ch, r),
+            None => a.ch.to_string(),
+        })
+        .collect()
+}
+
+/// 读音置于字上, 每个字占一格, 每行最多 `per_row` 字, 遇句读标点或换行时换行
+pub fn render_above(annotated: &[Annotated], per_row: usize) -> String {
+    let mut out = String::new();
+    let mut row: Vec<&Annotated> = vec![];
+    let flush = |row: &mut Vec<&Annotated>, out: &mut String| {
+        if row.is_empty() {
+            return;
+        }
+        let width = row
+            .iter()
+            .filter_map(|a| a.reading.as_ref())
+            .map(|r| r.width())
+            .chain(row.iter().map(|a| a.ch.width().unwrap_or(0)))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let mut top = String::new();
+        let mut bottom = String::new();
+        for a in row.iter() {
+            let reading = a.reading.as_deref().unwrap_or_default();
+            top.push_str(reading);
+            top.push_str(&" ".repeat(width - reading.width()));
+            bottom.push(a.ch);
+            bottom.push_str(&" ".repeat(width - a.ch.width().unwrap_or(0)));
+        }
+        out.push_str(top.trim_end());
+        out.push('\n');
+        out.push_str(bottom.trim_end());
+        out.push('\n');
+        row.clear();
+    };
+    for a in annotated {
+        if a.ch == '\n' {
+            flush(&mut row, &mut out);
+            continue;
+        }
+        row.push(a);
+        if row.len() >= per_row || "，。！？；".contains(a.ch) {
+            flush(&mut row, &mut out);
+        }
+    }
+    flush(&mut row, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings(text: &str, overrides: &HashMap<usize, String>) -> Vec<String> {
+        annotate(text, overrides)
+            .into_iter()
+            .filter_map(|a| a.reading)
+            .collect()
+    }
+
+    #[test]
+    fn test_annotate_polyphones() {
+        let none = HashMap::new();
+        assert_eq!(readings("将进酒", &none), vec!["qiāng", "jìn", "jiǔ"]);
+        assert_eq!(readings("远上寒山石径斜", &none)[6], "xiá");
+        assert_eq!(readings("千金散尽还复来", &none)[4], "huán");
+        assert_eq!(readings("将军", &none)[0], "jiāng");
+    }
+
+    #[test]
+    fn test_annotate_overrides() {
+        let mut overrides = HashMap::new();
+        overrides.insert(0, "jiàng".to_string());
+        assert_eq!(readings("将军", &overrides), vec!["jiàng", "jūn"]);
+    }
+
+    #[test]
+    fn test_render() {
+        let annotated = annotate("床前，", &HashMap::new());
+        assert_eq!(render_after(&annotated), "床(chuáng)前(qián)，");
+        assert_eq!(
+            render_above(&annotated, 10),
+            "chuáng qián\n床     前     ，\n"
+        );
+        // 半角字符只占一列
+        let annotated = annotate("a床", &HashMap::new());
+        assert_eq!(render_above(&annotated, 10), "       chuáng\na      床\n");
+    }
+}
@@ -0,0 +1,291 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::api::Article;
+use crate::corpus::Corpus;
+use crate::poem;
+use crate::tag::Tags;
+
+/// 同一作者的加分
+const WRITER_BONUS: f32 = 0.2;
+/// 每个共同标签的加分
+const TAG_BONUS: f32 = 0.15;
+/// 译文中的词语按此比例计入, 以正文为主
+const TRANSLATION_WEIGHT: f32 = 0.5;
+/// 每篇缓存的文本相近的文章数
+const CACHED: usize = 50;
+
+/// 一篇相关作品及推荐理由
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Related {
+    pub id: String,
+    pub title: String,
+    pub score: f32,
+    pub reasons: Vec<String>,
+}
+
+/// 正文与译文的字及相邻两字, 标点处断开
+fn terms(art: &Article) -> HashMap<String, f32> {
+    let mut counts = HashMap::new();
+    let texts = [
+        (art.content.as_str(), 1.0),
+        (art.translation.as_deref().unwrap_or(""), TRANSLATION_WEIGHT),
+    ];
+    for (text, weight) in texts.iter() {
+        for part in text.split(|c: char| !poem::is_text(c)) {
+            let chars: Vec<char> = part.chars().collect();
+            for c in chars.iter() {
+                *counts.entry(c.to_string()).or_insert(0.0) += weight;
+            }
+            for pair in chars.windows(2) {
+                *counts.entry(pair.iter().collect()).or_insert(0.0) += weight;
+            }
+        }
+    }
+    counts
+}
+
+/// 文集中各篇的词频与文档频率, 可逐篇加入
+#[derive(Debug, Default)]
+struct Model {
+    ids: Vec<String>,
+    positions: HashMap<String, usize>,
+    counts: Vec<HashMap<String, f32>>,
+    df: HashMap<String, usize>,
+}
+
+impl Model {
+    fn contains(&self, id: &str) -> bool {
+        self.positions.contains_key(id)
+    }
+
+    fn add(&mut self, art: &Article) {
+        let counts = terms(art);
+        for term in counts.keys() {
+            *self.df.entry(term.clone()).or_insert(0) += 1;
+        }
+        self.positions.insert(art.id.clone(), self.ids.len());
+        self.ids.push(art.id.clone());
+        self.counts.push(counts);
+    }
+
+    /// 按当前的文档频率计算 TF-IDF 向量, 已归一化
+    fn vector(&self, pos: usize) -> HashMap<String, f32> {
+        let n = self.ids.len() as f32;
+        let mut vector: HashMap<String, f32> = self.counts[pos]
+            .iter()
+            .map(|(term, tf)| {
+                let idf = ((n + 1.0) / (self.df[term] as f32 + 1.0)).ln() + 1.0;
+                (term.clone(), tf * idf)
+            })
+            .collect();
+        let norm = vector.values().map(|w| w * w).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.values_mut().for_each(|w| *w /= norm);
+        }
+        vector
+    }
+
+    /// 该篇与 `others` 中各篇的余弦相似度, 只保留大于 0 的
+    fn scores(&self, id: &str, others: &[usize]) -> Vec<(String, f32)> {
+        let target = match self.positions.get(id) {
+            Some(&pos) => self.vector(pos),
+            None => return vec![],
+        };
+        others
+            .iter()
+            .filter(|&&pos| self.ids[pos] != id)
+            .map(|&pos| {
+                let vector = self.vector(pos);
+                let score = vector
+                    .iter()
+                    .filter_map(|(term, w)| target.get(term).map(|v| w * v))
+                    .sum::<f32>();
+                (self.ids[pos].clone(), score)
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect()
+    }
+
+    /// 与该篇余弦相似度最高的文章, 不含该篇本身
+    fn similar(&self, id: &str, limit: usize) -> Vec<(String, f32)> {
+        let all: Vec<usize> = (0..self.ids.len()).collect();
+        let mut found = self.scores(id, &all);
+        found.sort_by(|a, b| b.1.total_cmp(&a.1));
+        found.truncate(limit);
+        found
+    }
+}
+
+/// 相关作品推荐, 文本相似度按文章 id 缓存. 文集新增文章时逐篇加入模型,
+/// 只把新文章并入已缓存的结果, 已缓存的分数不因文档频率的变化而重算
+#[derive(Debug, Default)]
+pub struct Recommender {
+    model: Model,
+    cache: HashMap<String, Vec<(String, f32)>>,
+}
+
+impl Recommender {
+    /// 把文集中新增的文章加入模型, 并与已缓存的各篇比较
+    fn update(&mut self, corpus: &Corpus) {
+        let first = self.model.ids.len();
+        for art in corpus.articles() {
+            if !self.model.contains(&art.id) {
+                self.model.add(art);
+            }
+        }
+        if self.model.ids.len() == first {
+            return;
+        }
+        let added: Vec<usize> = (first..self.model.ids.len()).collect();
+        for (id, similar) in self.cache.iter_mut() {
+            similar.extend(self.model.scores(id, &added));
+            similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+            similar.truncate(CACHED);
+        }
+    }
+
+    /// 综合文本相似度, 同一作者与共同标签, 列出最相关的几篇
+    pub fn related(
+        &mut self,
+        corpus: &Corpus,
+        tags: &Tags,
+        id: &str,
+        limit: usize,
+    ) -> Vec<Related> {
+        let art = match corpus.get(id) {
+            Some(art) => art,
+            None => return vec![],
+        };
+        self.update(corpus);
+        let model = &self.model;
+        let similar = self
+            .cache
+            .entry(id.to_string())
+            .or_insert_with(|| model.similar(id, CACHED));
+        let own_tags = tags.get(id);
+        let mut found: Vec<Related> = corpus
+            .articles()
+            .iter()
+            .filter(|other| other.id != id)
+            .filter_map(|other| {
+                let mut score = 0.0;
+                let mut reasons = vec![];
+                if let Some((_, sim)) = similar.iter().find(|(i, _)| *i == other.id) {
+                    score += sim;
+                    reasons.push(format!("文本相近 {:.2}", sim));
+                }
+                if !art.writer.is_empty() && other.writer == art.writer {
+                    score += WRITER_BONUS;
+                    reasons.push("同一作者".to_string());
+                }
+                let shared: Vec<&str> = tags
+                    .get(&other.id)
+                    .iter()
+                    .filter(|t| own_tags.contains(t))
+                    .map(String::as_str)
+                    .collect();
+                if !shared.is_empty() {
+                    score += TAG_BONUS * shared.len() as f32;
+                    reasons.push(format!("同标签 {}", shared.join("、")));
+                }
+                if reasons.is_empty() {
+                    return None;
+                }
+                Some(Related {
+                    id: other.id.clone(),
+                    title: other.title.clone(),
+                    score,
+                    reasons,
+                })
+            })
+            .collect();
+        found.sort_by(|a, b| b.score.total_cmp(&a.score));
+        found.truncate(limit);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ArticleSimple;
+    use crate::fixtures::JINGYESI;
+
+    fn corpus() -> Corpus {
+        let mut corpus = Corpus::default();
+        for (id, writer, content) in [
+            ("1", "李白", JINGYESI),
+            ("2", "杜甫", "露从今夜白，月是故乡明。"),
+            ("3", "孟浩然", "春眠不觉晓，处处闻啼鸟。"),
+            ("4", "李白", "朝辞白帝彩云间，千里江陵一日还。"),
+            ("5", "王维", "红豆生南国，春来发几枝。"),
+        ] {
+            corpus
+                .insert(Article {
+                    id: id.to_string(),
+                    title: format!("诗{}", id),
+                    writer: writer.to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        corpus
+    }
+
+    #[test]
+    fn test_similar() {
+        let corpus = corpus();
+        let mut model = Model::default();
+        corpus.articles().iter().for_each(|art| model.add(art));
+        let found = model.similar("1", 10);
+        assert_eq!(found[0].0, "2");
+        assert!(found.iter().all(|(id, _)| id != "1"));
+        assert!(model.similar("9", 10).is_empty());
+        // 向量已归一化, 与自身的相似度为 1
+        let v = model.vector(0);
+        let dot: f32 = v.values().map(|w| w * w).sum();
+        assert!((dot - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_related() {
+        let mut corpus = corpus();
+        let mut tags = Tags::default();
+        let tag = |id: &str| ArticleSimple {
+            id: id.to_string(),
+            ..Default::default()
+        };
+        tags.add(&tag("1"), "思乡");
+        tags.add(&tag("5"), "思乡");
+        let mut recommender = Recommender::default();
+        let found = recommender.related(&corpus, &tags, "1", 3);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].id, "2");
+        let ids: Vec<&str> = found.iter().map(|r| r.id.as_str()).collect();
+        assert!(ids.contains(&"4") && ids.contains(&"5"));
+        let writer = found.iter().find(|r| r.id == "4").unwrap();
+        assert!(writer.reasons.contains(&"同一作者".to_string()));
+        let tagged = found.iter().find(|r| r.id == "5").unwrap();
+        assert_eq!(tagged.reasons, vec!["同标签 思乡"]);
+        assert!(recommender.cache.contains_key("1"));
+
+        // 新增文章并入已有的缓存, 不清空其他篇的结果
+        recommender.related(&corpus, &tags, "3", 3);
+        let cached = recommender.cache["3"].clone();
+        corpus
+            .insert(Article {
+                id: "6".to_string(),
+                content: "举头望明月".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let found = recommender.related(&corpus, &tags, "1", 1);
+        assert_eq!(found[0].id, "6");
+        assert_eq!(recommender.cache.len(), 2);
+        assert_eq!(recommender.cache["3"], cached);
+        assert_eq!(recommender.model.ids.len(), 6);
+        assert!(recommender.related(&corpus, &tags, "9", 3).is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+use std::ops::Range;
+
+use crate::api::Article;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RemarkEntry {
+    /// 注释序号, 从 1 开始
+    pub index: usize,
+    /// 被注释的词语, 可能带有注音, 如 "将（qiāng）"
+    pub term: String,
+    pub explanation: String,
+}
+
+impl RemarkEntry {
+    /// 去掉注音等括注后的词语, 用于在正文中定位
+    pub fn keyword(&self) -> String {
+        let mut keyword = String::new();
+        let mut depth = 0usize;
+        for c in self.term.chars() {
+            match c {
+                '（' | '(' | '[' | '［' => depth += 1,
+                // 多余的右括号不影响之后的字
+                '）' | ')' | ']' | '］' => depth = depth.saturating_sub(1),
+                _ if depth == 0 && !c.is_whitespace() && !"“”‘’「」\"".contains(c) => {
+                    keyword.push(c)
+                }
+                _ => (),
+            }
+        }
+        keyword
+    }
+}
+
+/// 解析注释, 每行一条, 形如 "⑴君不见：你没有看见吗？"
+pub fn parse(remark: &str) -> Vec<RemarkEntry> {
+    let mut entries: Vec<RemarkEntry> = vec![];
+    for raw in remark.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line == "注释" || line == "注释：" {
+            continue;
+        }
+        let (number, rest) = strip_number(line);
+        let split = rest
+            .char_indices()
+            .find(|&(_, c)| c == '：' || c == ':')
+            .map(|(i, c)| (i, i + c.len_utf8()));
+        match split {
+            Some((colon, after)) if colon > 0 && (number.is_some() || colon <= 30) => {
+                entries.push(RemarkEntry {
+                    index: number.unwrap_or(entries.len() + 1),
+                    term: rest[..colon].trim().to_string(),
+                    explanation: rest[after..].trim().to_string(),
+                });
+            }
+            _ => match entries.last_mut() {
+                // 没有序号也没有冒号的行视为上一条注释的续行
+                Some(last) if number.is_none() => {
+                    last.explanation.push_str(rest);
+                }
+                _ => entries.push(RemarkEntry {
+                    index: number.unwrap_or(entries.len() + 1),
+                    term: String::new(),
+                    explanation: rest.to_string(),
+                }),
+            },
+        }
+    }
+    entries
+}
+
+/// 注释词语在正文中第一次出现的位置(字节区间)及对应的注释下标, 按位置排序且互不重叠
+pub fn locate(content: &str, entries: &[RemarkEntry]) -> Vec<(Range<usize>, usize)> {
+    let mut spans: Vec<(Range<usize>, usize)> = vec![];
+    for (idx, entry) in entries.iter().enumerate() {
+        let keyword = entry.keyword();
+        if keyword.is_empty() {
+            continue;
+        }
+        let from = spans.last().map_or(0, |(range, _)| range.start);
+        // 注释大体按正文顺序排列, 优先从上一条注释附近开始查找
+        let found = content[from..]
+            .find(&keyword)
+            .map(|i| i + from)
+            .or_else(|| content.find(&keyword));
+        if let Some(start) = found {
+            let range = start..start + keyword.len();
+            if spans
+                .iter()
+                .all(|(r, _)| range.end <= r.start || range.start >= r.end)
+            {
+                spans.push((range, idx));
+            }
+        }
+    }
+    spans.sort_by_key(|(range, _)| range.start);
+    spans
+}
+
+impl Article {
+    pub fn remarks(&self) -> Vec<RemarkEntry> {
+        self.remark.as_deref().map(parse).unwrap_or_default()
+    }
+}
+
+fn strip_number(line: &str) -> (Option<usize>, &str) {
+    if let Some(c) = line.chars().next() {
+        // ⑴-⒇ 与 ①-⑳
+        for &(base, count) in [('\u{2474}', 20), ('\u{2460}', 20)].iter() {
+            let offset = c as u32 as i64 - base as u32 as i64;
+            if (0..count).contains(&offset) {
+                return (Some(offset as usize + 1), line[c.len_utf8()..].trim_start());
+            }
+        }
+    }
+    let trimmed = line.trim_start_matches(&['(', '（', '[', '【'][..]);
+    let digits: String = trimmed.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        return (None, line);
+    }
+    let rest = &trimmed[digits.len()..];
+    match rest.chars().next() {
+        Some(c) if ")）]】.、．".contains(c) => {
+            (digits.parse().ok(), rest[c.len_utf8()..].trim_start())
+        }
+        _ => (None, line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remark() {
+        let entries = parse(
+            "⑴君不见：你没有看见吗？是乐府体诗中提唱的常用语。\n\
+             ⑵天上来：黄河发源于青海，因那里地势极高，故称。\n\
+             (3) 将（qiāng）：请。\n\
+             4.岑夫子：岑勋。\n\
+             丹丘生：元丹丘。\n\
+             二人均为李白的好友。",
+        );
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].index, 1);
+        assert_eq!(entries[0].term, "君不见");
+        assert_eq!(entries[2].index, 3);
+        assert_eq!(entries[2].keyword(), "将");
+        assert_eq!(entries[3].index, 4);
+        assert_eq!(entries[4].index, 5);
+        assert_eq!(entries[4].explanation, "元丹丘。二人均为李白的好友。");
+    }
+
+    #[test]
+    fn test_keyword_unbalanced() {
+        let entries = parse("⑴将）进酒：劝酒歌。");
+        assert_eq!(entries[0].keyword(), "将进酒");
+    }
+
+    #[test]
+    fn test_locate_terms() {
+        let content = "君不见黄河之水天上来，奔流到海不复回。将进酒，杯莫停。";
+        let entries = parse("⑴君不见：你没有看见吗？\n⑵天上来：黄河发源于青海。\n⑶将（qiāng）：请。\n⑷见黄河：看见黄河。");
+        let spans = locate(content, &entries);
+        let terms: Vec<&str> = spans.iter().map(|(r, _)| &content[r.clone()]).collect();
+        assert_eq!(terms, vec!["君不见", "天上来", "将"]);
+        assert_eq!(spans[2].1, 2);
+    }
+}
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::store;
+
+const ST_CHARACTERS: &str = include_str!("../assets/st_characters.txt");
+const ST_PHRASES: &str = include_str!("../assets/st_phrases.txt");
+const TW_VARIANTS: &str = include_str!("../assets/tw_variants.txt");
+const HK_VARIANTS: &str = include_str!("../assets/hk_variants.txt");
+const SCRIPT_FILE: &str = "script.json";
+
+/// 显示用字
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Script {
+    #[default]
+    Simplified,
+    Traditional,
+    Taiwan,
+    HongKong,
+}
+
+impl Script {
+    pub const ALL: [Script; 4] = [
+        Script::Simplified,
+        Script::Traditional,
+        Script::Taiwan,
+        Script::HongKong,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Script::Simplified => "简体",
+            Script::Traditional => "繁体",
+            Script::Taiwan => "台湾正体",
+            Script::HongKong => "香港繁体",
+        }
+    }
+
+    pub fn load() -> Script {
+        store::load(SCRIPT_FILE)
+    }
+
+    pub fn save(self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(SCRIPT_FILE, &self)
+    }
+}
+
+/// 简繁转换器, 词组优先, 其次单字, 最后替换地区异体字
+#[derive(Debug, Default)]
+pub struct Converter {
+    chars: HashMap<char, char>,
+    phrases: HashMap<String, String>,
+    max_phrase: usize,
+    taiwan: HashMap<char, char>,
+    hongkong: HashMap<char, char>,
+    /// 繁体(含异体)到简体, 用于统一检索输入
+    simplified: HashMap<char, char>,
+}
+
+/// 解析 OpenCC 格式的对照表, 每行为 "源\t目标 目标2 ..."
+fn parse_table(text: &str) -> Vec<(&str, Vec<&str>)> {
+    text.lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .filter_map(|l| {
+            let (from, to) = l.split_once('\t')?;
+            Some((from, to.split_whitespace().collect()))
+        })
+        .collect()
+}
+
+fn first_char(s: &str) -> Option<char> {
+    s.chars().next()
+}
+
+impl Converter {
+    pub fn bundled() -> Converter {
+        let mut conv = Converter::default();
+        for (from, to) in parse_table(ST_CHARACTERS) {
+            let s = match first_char(from) {
+                Some(s) => s,
+                None => continue,
+            };
+            if let Some(t) = to.first().and_then(|t| first_char(t)) {
+                conv.chars.insert(s, t);
+            }
+            for t in to.iter().filter_map(|t| first_char(t)) {
+                conv.simplified.entry(t).or_insert(s);
+            }
+        }
+        for (from, to) in parse_table(ST_PHRASES) {
+            if let Some(t) = to.first() {
+                conv.max_phrase = conv.max_phrase.max(from.chars().count());
+                conv.phrases.insert(from.to_string(), t.to_string());
+            }
+        }
+        for (table, text) in [
+            (&mut conv.taiwan, TW_VARIANTS),
+            (&mut conv.hongkong, HK_VARIANTS),
+        ] {
+            for (from, to) in parse_table(text) {
+                if let (Some(f), Some(t)) =
+                    (first_char(from), to.first().and_then(|t| first_char(t)))
+                {
+                    table.insert(f, t);
+                }
+            }
+        }
+        // 异体字先还原为通用繁体再转简体
+        let variants: Vec<(char, char)> = conv
+            .taiwan
+            .iter()
+            .chain(conv.hongkong.iter())
+            .map(|(&base, &variant)| (variant, base))
+            .collect();
+        for (variant, base) in variants {
+            let s = conv.simplified.get(&base).copied().unwrap_or(base);
+            conv.simplified.entry(variant).or_insert(s);
+        }
+        conv
+    }
+
+    pub fn convert(&self, text: &str, script: Script) -> String {
+        if script == Script::Simplified {
+            return text.to_string();
+        }
+        let variants = match script {
+            Script::Taiwan => Some(&self.taiwan),
+            Script::HongKong => Some(&self.hongkong),
+            _ => None,
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let phrase = (2..=self.max_phrase.min(chars.len() - i))
+                .rev()
+                .find_map(|len| {
+                    let key: String = chars[i..i + len].iter().collect();
+                    self.phrases.get(&key).map(|t| (len, t))
+                });
+            let converted: Vec<char> = match phrase {
+                Some((len, t)) => {
+                    i += len;
+                    t.chars().collect()
+                }
+                None => {
+                    let c = chars[i];
+                    i += 1;
+                    vec![self.chars.get(&c).copied().unwrap_or(c)]
+                }
+            };
+            for c in converted {
+                out.push(variants.and_then(|v| v.get(&c)).copied().unwrap_or(c));
+            }
+        }
+        out
+    }
+
+    /// 将繁体或简体输入统一为简体, 以便与文集匹配
+    pub fn to_simplified(&self, text: &str) -> String {
+        text.chars()
+            .map(|c| self.simplified.get(&c).copied().unwrap_or(c))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let conv = Converter::bundled();
+        assert_eq!(
+            conv.convert("君不见高堂明镜悲白发", Script::Traditional),
+            "君不見高堂明鏡悲白髮"
+        );
+        assert_eq!(conv.convert("发现", Script::Traditional), "發現");
+        assert_eq!(conv.convert("千里莺啼", Script::Traditional), "千里鶯啼");
+        assert_eq!(conv.convert("为君", Script::Traditional), "爲君");
+        assert_eq!(conv.convert("为君", Script::Taiwan), "為君");
+        assert_eq!(conv.convert("说", Script::HongKong), "説");
+        assert_eq!(conv.convert("说", Script::Simplified), "说");
+    }
+
+    #[test]
+    fn test_to_simplified() {
+        let conv = Converter::bundled();
+        assert_eq!(conv.to_simplified("悲白髮"), "悲白发");
+        assert_eq!(conv.to_simplified("為君"), "为君");
+        assert_eq!(conv.to_simplified("説"), "说");
+        assert_eq!(conv.to_simplified("明月"), "明月");
+    }
+}
@@ -0,0 +1,90 @@
+use regex::Regex;
+
+use crate::api::ArticleSimple;
+use crate::corpus::Corpus;
+use crate::poem::{Line, CN_PUNCTIONS};
+
+/// 诗句检索模式, 逐句匹配去掉标点后的诗句
+#[derive(Debug)]
+pub struct LinePattern(Regex);
+
+impl LinePattern {
+    /// 以 `/` 包围的输入按正则表达式处理, 如 `/明月.*照/`;
+    /// 其余按通配符处理, `?` 匹配一个字, `*` 匹配任意个字, 须与整句吻合, 如 `?月?花`
+    pub fn parse(input: &str) -> Result<LinePattern, regex::Error> {
+        let input = input.trim();
+        if input.len() > 2 && input.starts_with('/') && input.ends_with('/') {
+            return Ok(LinePattern(Regex::new(&input[1..input.len() - 1])?));
+        }
+        let mut re = String::from("^");
+        for c in input.chars() {
+            match c {
+                '?' | '？' => re.push('.'),
+                '*' | '＊' => re.push_str(".*"),
+                _ if c.is_whitespace() || CN_PUNCTIONS.contains(&c) => (),
+                _ => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        re.push('$');
+        Ok(LinePattern(Regex::new(&re)?))
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.0.is_match(line)
+    }
+}
+
+/// 在文集中逐句检索, 每篇文章一条结果, 匹配句所在的整联作为上下文
+pub fn search_lines(corpus: &Corpus, pattern: &LinePattern) -> Vec<ArticleSimple> {
+    let mut found = vec![];
+    for art in corpus.articles() {
+        let poem = art.poem();
+        let contexts: Vec<String> = poem
+            .couplets()
+            .into_iter()
+            .filter(|couplet| couplet.iter().any(|l| pattern.is_match(&l.text)))
+            .map(|couplet| couplet.iter().map(Line::with_punct).collect())
+            .collect();
+        if !contexts.is_empty() {
+            found.push(ArticleSimple {
+                id: art.id.clone(),
+                title: art.title.clone(),
+                snippet: Some(contexts.join("\n")),
+            });
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::jingyesi;
+
+    #[test]
+    fn test_wildcard_and_regex() {
+        let pattern = LinePattern::parse("?月?花").unwrap();
+        assert!(pattern.is_match("春月落花"));
+        assert!(!pattern.is_match("春江花月"));
+        assert!(!pattern.is_match("江月照花林"));
+        let pattern = LinePattern::parse("明月*").unwrap();
+        assert!(pattern.is_match("明月几时有"));
+        assert!(!pattern.is_match("举头望明月"));
+        let pattern = LinePattern::parse("/望.*月/").unwrap();
+        assert!(pattern.is_match("举头望明月"));
+        assert!(LinePattern::parse("/[/").is_err());
+    }
+
+    #[test]
+    fn test_search_lines() {
+        let mut corpus = Corpus::default();
+        corpus.insert(jingyesi()).unwrap();
+        let found = search_lines(&corpus, &LinePattern::parse("??望明?").unwrap());
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].snippet.as_deref(),
+            Some("举头望明月，低头思故乡。")
+        );
+        assert!(search_lines(&corpus, &LinePattern::parse("明月").unwrap()).is_empty());
+    }
+}
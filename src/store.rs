@@ -0,0 +1,34 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 本地数据目录, 可通过 `ENLIGHTEN_HOME` 环境变量指定, 默认为 `~/.enlighten`
+pub fn data_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("ENLIGHTEN_HOME") {
+        return PathBuf::from(dir);
+    }
+    match std::env::var("HOME") {
+        Ok(home) => Path::new(&home).join(".enlighten"),
+        Err(_) => PathBuf::from(".enlighten"),
+    }
+}
+
+/// 读取数据目录下的 JSON 文件, 文件不存在或损坏时返回默认值
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = data_dir().join(name);
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("failed to parse {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use pinyin::ToPinyin;
+use std::collections::HashMap;
+
+use crate::corpus::Corpus;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Title,
+    Writer,
+    Dynasty,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Title => "标题",
+            Kind::Writer => "作者",
+            Kind::Dynasty => "朝代",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub kind: Kind,
+    pub text: String,
+    /// 热度: 作者与朝代为文集中的篇数, 标题沿用其作者的热度
+    pub popularity: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    suggestion: Suggestion,
+    /// 不带声调的全拼, 如 "jiangjinjiu"
+    full: String,
+    /// 拼音首字母, 如 "jjj"
+    initials: String,
+}
+
+/// 标题, 作者与朝代的拼音索引, 用于输入拼音或首字母时补全
+#[derive(Debug, Default)]
+pub struct Suggester {
+    entries: Vec<Entry>,
+}
+
+impl Suggester {
+    pub fn build(corpus: &Corpus) -> Suggester {
+        let mut writers: HashMap<&str, usize> = HashMap::new();
+        let mut dynasties: HashMap<&str, usize> = HashMap::new();
+        for art in corpus.articles() {
+            *writers.entry(art.writer.as_str()).or_default() += 1;
+            *dynasties.entry(art.dynasty.as_str()).or_default() += 1;
+        }
+        let mut suggester = Suggester::default();
+        let mut titles: HashMap<&str, usize> = HashMap::new();
+        for art in corpus.articles() {
+            let popularity = writers[art.writer.as_str()];
+            let best = titles.entry(art.title.as_str()).or_default();
+            *best = (*best).max(popularity);
+        }
+        for (kind, names) in [
+            (Kind::Title, titles),
+            (Kind::Writer, writers),
+            (Kind::Dynasty, dynasties),
+        ] {
+            for (text, popularity) in names {
+                if !text.is_empty() {
+                    suggester.push(kind, text, popularity);
+                }
+            }
+        }
+        suggester
+    }
+
+    fn push(&mut self, kind: Kind, text: &str, popularity: usize) {
+        let mut full = String::new();
+        let mut initials = String::new();
+        for c in text.chars() {
+            match c.to_pinyin() {
+                Some(p) => {
+                    full.push_str(p.plain());
+                    initials.push_str(p.first_letter());
+                }
+                None if c.is_ascii_alphanumeric() => {
+                    full.push(c.to_ascii_lowercase());
+                    initials.push(c.to_ascii_lowercase());
+                }
+                None => (),
+            }
+        }
+        self.entries.push(Entry {
+            suggestion: Suggestion {
+                kind,
+                text: text.to_string(),
+                popularity,
+            },
+            full,
+            initials,
+        });
+    }
+
+    /// 按全拼前缀, 首字母前缀或汉字包含匹配, 全拼匹配优先, 其次按热度排序
+    pub fn suggest(&self, input: &str, limit: usize) -> Vec<Suggestion> {
+        let input: String = input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '\'')
+            .collect::<String>()
+            .to_lowercase();
+        if input.is_empty() {
+            return vec![];
+        }
+        let mut matched: Vec<(u8, &Suggestion)> = self
+            .entries
+            .iter()
+            .filter_map(|e| {
+                let rank = if !input.is_ascii() {
+                    e.suggestion.text.contains(&input).then_some(0)
+                } else if e.full.starts_with(&input) {
+                    Some(0)
+                } else if e.initials.starts_with(&input) {
+                    Some(1)
+                } else {
+                    None
+                };
+                rank.map(|r| (r, &e.suggestion))
+            })
+            .collect();
+        matched.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then(b.1.popularity.cmp(&a.1.popularity))
+                .then(a.1.text.chars().count().cmp(&b.1.text.chars().count()))
+                .then(a.1.text.cmp(&b.1.text))
+        });
+        matched
+            .into_iter()
+            .take(limit)
+            .map(|(_, s)| s.clone())
+            .collect()
+    }
+
+    /// 将拼音输入解析为指定类别中最匹配的名称, 已是汉字的输入原样返回
+    pub fn resolve(&self, input: &str, kind: Kind) -> Option<String> {
+        if !input.is_ascii() {
+            return Some(input.to_string());
+        }
+        self.suggest(input, usize::MAX)
+            .into_iter()
+            .find(|s| s.kind == kind)
+            .map(|s| s.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Article;
+
+    fn suggester() -> Suggester {
+        let mut corpus = Corpus::default();
+        for (id, title, writer, dynasty) in [
+            ("1", "将进酒", "李白", "唐代"),
+            ("2", "静夜思", "李白", "唐代"),
+            ("3", "江南春", "杜牧", "唐代"),
+            ("4", "江城子·密州出猎", "苏轼", "宋代"),
+        ] {
+            corpus
+                .insert(Article {
+                    id: id.to_string(),
+                    title: title.to_string(),
+                    writer: writer.to_string(),
+                    dynasty: dynasty.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        Suggester::build(&corpus)
+    }
+
+    #[test]
+    fn test_suggest() {
+        let suggester = suggester();
+        let texts = |input: &str| -> Vec<String> {
+            suggester
+                .suggest(input, 10)
+                .into_iter()
+                .map(|s| s.text)
+                .collect()
+        };
+        assert_eq!(texts("jiangjinjiu"), vec!["将进酒"]);
+        assert_eq!(texts("jjj"), vec!["将进酒"]);
+        // 全拼优先, 同级按热度
+        assert_eq!(texts("jiang"), vec!["将进酒", "江南春", "江城子·密州出猎"]);
+        assert_eq!(texts("lb"), vec!["李白"]);
+        assert_eq!(texts("静"), vec!["静夜思"]);
+        assert!(texts("").is_empty());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let suggester = suggester();
+        assert_eq!(
+            suggester.resolve("libai", Kind::Writer).as_deref(),
+            Some("李白")
+        );
+        assert_eq!(
+            suggester.resolve("sd", Kind::Dynasty).as_deref(),
+            Some("宋代")
+        );
+        assert_eq!(
+            suggester.resolve("李白", Kind::Writer).as_deref(),
+            Some("李白")
+        );
+        assert_eq!(suggester.resolve("xyz", Kind::Title), None);
+    }
+}
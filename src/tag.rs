@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::api::{ArtListResp, ArticleSimple, Method};
+use crate::store;
+
+const TAGS_FILE: &str = "tags.json";
+
+/// 打了标签的文章, 记下标题以便离线列出
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tagged {
+    pub id: String,
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+/// 用户给文章打的标签, 按首次打标签的先后排列, 导入导出使用同样的格式
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tags {
+    articles: Vec<Tagged>,
+}
+
+impl Tags {
+    pub fn load() -> Tags {
+        store::load(TAGS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        store::save(TAGS_FILE, self)
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Tags, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn get(&self, id: &str) -> &[String] {
+        self.articles
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// 给文章加上标签, 返回是否为新加的
+    pub fn add(&mut self, art: &ArticleSimple, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || art.id.is_empty() {
+            return false;
+        }
+        let pos = match self.articles.iter().position(|t| t.id == art.id) {
+            Some(pos) => pos,
+            None => {
+                self.articles.push(Tagged {
+                    id: art.id.clone(),
+                    title: art.title.clone(),
+                    tags: vec![],
+                });
+                self.articles.len() - 1
+            }
+        };
+        let tags = &mut self.articles[pos].tags;
+        if tags.iter().any(|t| t == tag) {
+            return false;
+        }
+        tags.push(tag.to_string());
+        true
+    }
+
+    /// 去掉文章的标签, 没有标签的文章不再记录
+    pub fn remove(&mut self, id: &str, tag: &str) -> bool {
+        let mut removed = false;
+        for tagged in self.articles.iter_mut().filter(|t| t.id == id) {
+            let len = tagged.tags.len();
+            tagged.tags.retain(|t| t != tag);
+            removed = tagged.tags.len() < len;
+        }
+        self.articles.retain(|t| !t.tags.is_empty());
+        removed
+    }
+
+    /// 全部标签及其文章数, 按文章数从多到少排列
+    pub fn names(&self) -> Vec<(String, usize)> {
+        let mut names: Vec<(String, usize)> = vec![];
+        for tag in self.articles.iter().flat_map(|t| t.tags.iter()) {
+            match names.iter_mut().find(|(name, _)| name == tag) {
+                Some((_, count)) => *count += 1,
+                None => names.push((tag.clone(), 1)),
+            }
+        }
+        names.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        names
+    }
+
+    /// 并入导入的标签, 返回新加的标签数
+    pub fn merge(&mut self, other: Tags) -> usize {
+        let mut added = 0;
+        for tagged in other.articles {
+            let art = ArticleSimple {
+                id: tagged.id,
+                title: tagged.title,
+                snippet: None,
+            };
+            for tag in tagged.tags.iter() {
+                if self.add(&art, tag) {
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+
+    /// 按页列出带有该标签的文章, 匹配处为文章的全部标签
+    pub fn page(&self, tag: &str, page: usize) -> ArtListResp {
+        let found = self
+            .articles
+            .iter()
+            .filter(|t| t.tags.iter().any(|t| t == tag))
+            .map(|t| ArticleSimple {
+                id: t.id.clone(),
+                title: t.title.clone(),
+                snippet: Some(t.tags.join("、")),
+            })
+            .collect();
+        ArtListResp::paginate(found, page, Method::Tag(tag.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(id: &str, title: &str) -> ArticleSimple {
+        ArticleSimple {
+            id: id.to_string(),
+            title: title.to_string(),
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_add_and_page() {
+        let mut tags = Tags::default();
+        assert!(tags.add(&article("1", "静夜思"), " 思乡 "));
+        assert!(!tags.add(&article("1", "静夜思"), "思乡"));
+        assert!(!tags.add(&article("1", "静夜思"), " "));
+        assert!(tags.add(&article("1", "静夜思"), "月"));
+        assert!(tags.add(&article("2", "出塞"), "边塞"));
+        assert!(tags.add(&article("3", "月夜忆舍弟"), "思乡"));
+        assert_eq!(tags.get("1"), ["思乡", "月"]);
+        assert_eq!(
+            tags.names(),
+            vec![
+                ("思乡".to_string(), 2),
+                ("月".to_string(), 1),
+                ("边塞".to_string(), 1)
+            ]
+        );
+
+        let resp = tags.page("思乡", 1);
+        assert_eq!(resp.total, 2);
+        assert!(matches!(resp.method, Method::Tag(ref tag) if tag == "思乡"));
+        assert_eq!(resp.data[0].title, "静夜思");
+        assert_eq!(resp.data[0].snippet.as_deref(), Some("思乡、月"));
+        assert!(tags.page("思乡", 2).data.is_empty());
+
+        assert!(tags.remove("2", "边塞"));
+        assert!(!tags.remove("2", "边塞"));
+        assert!(tags.get("2").is_empty());
+        assert_eq!(tags.names().len(), 2);
+    }
+
+    #[test]
+    fn test_export_and_merge() {
+        let mut tags = Tags::default();
+        tags.add(&article("1", "静夜思"), "思乡");
+        let json = tags.to_json().unwrap();
+        let parsed: Tags = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, tags);
+
+        let mut other = Tags::default();
+        other.add(&article("1", "静夜思"), "思乡");
+        other.add(&article("1", "静夜思"), "月");
+        other.add(&article("4", "送友人"), "送别");
+        assert_eq!(tags.merge(other), 2);
+        assert_eq!(tags.get("1"), ["思乡", "月"]);
+        assert_eq!(tags.page("送别", 1).data[0].title, "送友人");
+    }
+}
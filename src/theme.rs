@@ -0,0 +1,243 @@
+use serde::Serialize;
+use std::cmp::Reverse;
+use thiserror::Error;
+
+use crate::api::{Article, ArticleSimple};
+use crate::corpus::Corpus;
+
+const THEMES: &str = include_str!("../assets/themes.txt");
+/// 得分达到该值才判为该题材
+const THRESHOLD: u32 = 4;
+/// 线索词出现在正文中时的权重倍数
+const CONTENT_FACTOR: u32 = 2;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ThemeError {
+    #[error("未知题材: {0}")]
+    Unknown(String),
+}
+
+/// 判定的题材, 附带得分与命中的线索词
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Label {
+    pub theme: String,
+    pub score: u32,
+    /// 按贡献从大到小排列
+    pub evidence: Vec<String>,
+}
+
+impl Label {
+    /// 如 "思乡(故乡、明月)"
+    pub fn describe(&self) -> String {
+        format!("{}({})", self.theme, self.evidence.join("、"))
+    }
+}
+
+/// 各题材的线索词及权重
+#[derive(Debug, Default)]
+pub struct Lexicon {
+    themes: Vec<(String, Vec<(String, u32)>)>,
+}
+
+impl Lexicon {
+    pub fn bundled() -> Lexicon {
+        Lexicon::parse(THEMES)
+    }
+
+    pub fn parse(text: &str) -> Lexicon {
+        let mut themes = vec![];
+        for line in text.lines().filter(|l| !l.starts_with('#')) {
+            let mut parts = line.split_whitespace();
+            let name = match parts.next() {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let terms = parts
+                .map(|part| match part.split_once('*') {
+                    Some((term, weight)) => (term.to_string(), weight.parse().unwrap_or(1)),
+                    None => (part.to_string(), 1),
+                })
+                .collect();
+            themes.push((name, terms));
+        }
+        Lexicon { themes }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.themes.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    fn find(&self, name: &str) -> Result<&str, ThemeError> {
+        self.names()
+            .into_iter()
+            .find(|n| *n == name.trim())
+            .ok_or_else(|| ThemeError::Unknown(name.to_string()))
+    }
+
+    /// 按正文, 翻译与赏析判定题材, 得分高的在前
+    pub fn classify(&self, art: &Article) -> Vec<Label> {
+        let notes = [art.translation.as_deref(), art.shangxi.as_deref()];
+        let mut labels: Vec<Label> = vec![];
+        for (name, terms) in self.themes.iter() {
+            let mut hits: Vec<(&str, u32)> = terms
+                .iter()
+                .filter_map(|(term, weight)| {
+                    if art.content.contains(term.as_str()) {
+                        Some((term.as_str(), weight * CONTENT_FACTOR))
+                    } else if notes
+                        .iter()
+                        .flatten()
+                        .any(|text| text.contains(term.as_str()))
+                    {
+                        Some((term.as_str(), *weight))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let score = hits.iter().map(|(_, w)| w).sum();
+            if score < THRESHOLD {
+                continue;
+            }
+            hits.sort_by_key(|(_, weight)| Reverse(*weight));
+            labels.push(Label {
+                theme: name.clone(),
+                score,
+                evidence: hits.iter().map(|(term, _)| term.to_string()).collect(),
+            });
+        }
+        labels.sort_by_key(|l| Reverse(l.score));
+        labels
+    }
+
+    /// 本地文集中各题材的文章数
+    pub fn counts(&self, corpus: &Corpus) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> =
+            self.names().iter().map(|n| (n.to_string(), 0)).collect();
+        for art in corpus.articles() {
+            for label in self.classify(art) {
+                if let Some((_, count)) = counts.iter_mut().find(|(n, _)| *n == label.theme) {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// 本地文集中属于任一题材的文章, 命中题材多的在前, 其次按得分排列,
+    /// 匹配处为命中的题材及线索词. 以一篇文章的全部题材检索即得同类作品
+    pub fn search(&self, corpus: &Corpus, query: &str) -> Result<Vec<ArticleSimple>, ThemeError> {
+        let wanted = parse_query(query)
+            .iter()
+            .map(|name| self.find(name))
+            .collect::<Result<Vec<&str>, ThemeError>>()?;
+        if wanted.is_empty() {
+            return Err(ThemeError::Unknown(query.to_string()));
+        }
+        let mut found: Vec<(usize, u32, ArticleSimple)> = vec![];
+        for art in corpus.articles() {
+            let labels: Vec<Label> = self
+                .classify(art)
+                .into_iter()
+                .filter(|l| wanted.contains(&l.theme.as_str()))
+                .collect();
+            if labels.is_empty() {
+                continue;
+            }
+            let score = labels.iter().map(|l| l.score).sum();
+            let snippet = labels
+                .iter()
+                .map(Label::describe)
+                .collect::<Vec<_>>()
+                .join(" · ");
+            found.push((
+                labels.len(),
+                score,
+                ArticleSimple {
+                    snippet: Some(snippet),
+                    ..art.to_simple()
+                },
+            ));
+        }
+        found.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        Ok(found.into_iter().map(|(_, _, art)| art).collect())
+    }
+}
+
+/// 检索式中以空格, 顿号或逗号分隔的题材名
+pub fn parse_query(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| c.is_whitespace() || "、,，".contains(c))
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::JINGYESI;
+
+    fn article(id: &str, content: &str, shangxi: Option<&str>) -> Article {
+        Article {
+            id: id.to_string(),
+            title: id.to_string(),
+            content: content.to_string(),
+            shangxi: shangxi.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let lexicon = Lexicon::bundled();
+        assert_eq!(lexicon.names().len(), 7);
+        let art = article("1", JINGYESI, Some("这是一首写远客思乡之情的诗。"));
+        let labels = lexicon.classify(&art);
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].theme, "思乡");
+        // 正文中的 "故乡" 2 * 2, 赏析中的 "思乡" 3
+        assert_eq!(labels[0].score, 7);
+        assert_eq!(labels[0].evidence, vec!["故乡", "思乡"]);
+        assert_eq!(labels[0].describe(), "思乡(故乡、思乡)");
+
+        // 只有赏析中的一个弱线索时不判定
+        let art = article("2", "春眠不觉晓，处处闻啼鸟。", Some("诗人怀念故人。"));
+        assert!(lexicon.classify(&art).is_empty());
+
+        let lexicon = Lexicon::parse("# 注释\n测试 甲*4 乙\n");
+        assert_eq!(
+            lexicon.themes[0].1,
+            vec![("甲".to_string(), 4), ("乙".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let lexicon = Lexicon::bundled();
+        let mut corpus = Corpus::default();
+        corpus
+            .insert(article("1", "劝君更尽一杯酒，西出阳关无故人。", None))
+            .unwrap();
+        corpus
+            .insert(article("2", "羌笛何须怨杨柳，春风不度玉门关。", None))
+            .unwrap();
+        corpus
+            .insert(article("3", "故乡今夜思千里，霜鬓明朝又一年。", None))
+            .unwrap();
+        let found = lexicon.search(&corpus, "送别、边塞").unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].id, "1");
+        assert!(found[0]
+            .snippet
+            .as_deref()
+            .unwrap()
+            .starts_with("送别(劝君"));
+        assert_eq!(
+            lexicon.search(&corpus, "咏史").unwrap_err(),
+            ThemeError::Unknown("咏史".to_string())
+        );
+        let counts = lexicon.counts(&corpus);
+        assert_eq!(counts[0], ("思乡".to_string(), 1));
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+
+use crate::align;
+use crate::api::Article;
+use crate::note::Notes;
+use crate::poem;
+use crate::reading::{self, Annotated, ReadingOverrides};
+use crate::remark::{self, RemarkEntry};
+
+const LATEX_PREAMBLE: &str = r"\documentclass[UTF8]{ctexart}
+\usepackage[a4paper,margin=2.5cm]{geometry}
+\usepackage{ruby}
+\usepackage{paracol}
+\renewcommand{\rubysep}{-0.3ex}
+\setlength{\columnsep}{2em}
+";
+
+const TYPST_PREAMBLE: &str = r#"#set page(paper: "a4", margin: 2.5cm)
+#set text(font: ("Noto Serif CJK SC", "Source Han Serif SC"), lang: "zh")
+#let ruby(rt, body) = box(align(center + bottom, stack(spacing: 0.2em, text(size: 0.5em, rt), body)))
+"#;
+
+/// 排版后的正文片段
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Char(Annotated),
+    /// 注释词语之后的脚注
+    Note(String),
+    Break,
+}
+
+/// 原文与译文并排的一行, 能按联对齐时每联一行, 否则全文一行
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    original: Vec<Piece>,
+    translation: String,
+}
+
+fn note(entry: &RemarkEntry) -> String {
+    if entry.term.is_empty() {
+        entry.explanation.clone()
+    } else {
+        format!("{}：{}", entry.term, entry.explanation)
+    }
+}
+
+/// 逐字注音并在注释词语后插入脚注, 返回各行与未能在正文中定位的注释
+fn rows(art: &Article, readings: &ReadingOverrides) -> (Vec<Row>, Vec<RemarkEntry>) {
+    let annotated = reading::annotate(&art.content, &readings.get(&art.id));
+    let entries = art.remarks();
+    let spans = remark::locate(&art.content, &entries);
+    // 以注释词语最后一个字的字序为键
+    let mut notes: HashMap<usize, Vec<String>> = HashMap::new();
+    for (range, idx) in spans.iter() {
+        let last = art.content[..range.end].chars().count() - 1;
+        notes.entry(last).or_default().push(note(&entries[*idx]));
+    }
+    let unplaced = entries
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| spans.iter().all(|(_, idx)| idx != i))
+        .map(|(_, e)| e.clone())
+        .collect();
+
+    let mut pieces = vec![];
+    for (i, a) in annotated.into_iter().enumerate() {
+        if a.ch == '\n' {
+            if !pieces.is_empty() && pieces.last() != Some(&Piece::Break) {
+                pieces.push(Piece::Break);
+            }
+            continue;
+        }
+        if a.ch.is_whitespace() {
+            continue;
+        }
+        pieces.push(Piece::Char(a));
+        for n in notes.remove(&i).unwrap_or_default() {
+            pieces.push(Piece::Note(n));
+        }
+    }
+    while pieces.last() == Some(&Piece::Break) {
+        pieces.pop();
+    }
+
+    let translation = art.translation.as_deref().unwrap_or("").trim();
+    let pairs = align::align(&art.poem(), translation);
+    let rows = match pairs {
+        Some(pairs) => {
+            let quotas: Vec<usize> = pairs
+                .iter()
+                .map(|p| poem::normalize(&p.original).chars().count())
+                .collect();
+            let mut rows: Vec<Row> = pairs
+                .into_iter()
+                .map(|p| Row {
+                    original: vec![],
+                    translation: p.translation,
+                })
+                .collect();
+            let (mut row, mut used) = (0, 0);
+            for piece in pieces {
+                match &piece {
+                    Piece::Break => continue,
+                    Piece::Char(a) if poem::is_text(a.ch) => {
+                        if used >= quotas[row] && row + 1 < rows.len() {
+                            row += 1;
+                            used = 0;
+                        }
+                        used += 1;
+                    }
+                    _ => (),
+                }
+                rows[row].original.push(piece);
+            }
+            rows
+        }
+        None => vec![Row {
+            original: pieces,
+            translation: translation.to_string(),
+        }],
+    };
+    (rows, unplaced)
+}
+
+fn latex_escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str(r"\textbackslash{}"),
+            '~' => out.push_str(r"\textasciitilde{}"),
+            '^' => out.push_str(r"\textasciicircum{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn latex_pieces(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Char(Annotated {
+                ch,
+                reading: Some(r),
+            }) if poem::is_text(*ch) => format!(r"\ruby{{{}}}{{{}}}", ch, latex_escape(r)),
+            Piece::Char(a) => latex_escape(&a.ch.to_string()),
+            Piece::Note(n) => format!(r"\footnote{{{}}}", latex_escape(n)),
+            Piece::Break => "\\\\\n".to_string(),
+        })
+        .collect()
+}
+
+fn paragraphs(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+fn latex_article(
+    art: &Article,
+    readings: &ReadingOverrides,
+    notes: &Notes,
+    numbered: bool,
+) -> String {
+    let star = if numbered { "" } else { "*" };
+    let mut out = format!(
+        "\\section{}{{{}}}\n\\begin{{center}}{}\\end{{center}}\n\n",
+        star,
+        latex_escape(&art.title),
+        latex_escape(&art.byline())
+    );
+    let (rows, unplaced) = rows(art, readings);
+    let parallel = rows.iter().any(|r| !r.translation.is_empty());
+    if parallel {
+        out.push_str("\\begin{paracol}{2}\n");
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                out.push_str("\\switchcolumn*\n");
+            }
+            out.push_str(&format!(
+                "{}\n\\switchcolumn\n{}\n",
+                latex_pieces(&row.original),
+                paragraphs(&row.translation)
+                    .iter()
+                    .map(|p| latex_escape(p))
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            ));
+        }
+        out.push_str("\\end{paracol}\n\n");
+    } else {
+        for row in rows {
+            out.push_str(&format!("{}\n\n", latex_pieces(&row.original)));
+        }
+    }
+    if !unplaced.is_empty() {
+        out.push_str("\\subsection*{注释}\n\\begin{itemize}\n");
+        for entry in unplaced {
+            out.push_str(&format!("\\item {}\n", latex_escape(&note(&entry))));
+        }
+        out.push_str("\\end{itemize}\n\n");
+    }
+    if let Some(shangxi) = art.shangxi.as_deref().filter(|s| !s.trim().is_empty()) {
+        out.push_str("\\subsection*{赏析}\n");
+        for p in paragraphs(shangxi) {
+            out.push_str(&format!("{}\n\n", latex_escape(p)));
+        }
+    }
+    let own = notes.get(&art.id);
+    if !own.is_empty() {
+        let poem = art.poem();
+        out.push_str("\\subsection*{笔记}\n\\begin{itemize}\n");
+        for n in own {
+            out.push_str(&format!(
+                "\\item {}：{}\n",
+                latex_escape(&n.label(&poem)),
+                latex_escape(&n.text)
+            ));
+        }
+        out.push_str("\\end{itemize}\n\n");
+    }
+    out
+}
+
+/// LaTeX 文档, 需用 XeLaTeX 编译: 拼音以 ruby 宏注于字上, 注释为脚注, 译文与原文分栏并排
+pub fn latex(
+    articles: &[Article],
+    title: &str,
+    readings: &ReadingOverrides,
+    notes: &Notes,
+) -> String {
+    let mut out = LATEX_PREAMBLE.to_string();
+    out.push_str("\\begin{document}\n");
+    let numbered = articles.len() > 1;
+    if numbered {
+        out.push_str(&format!(
+            "\\title{{{}}}\n\\date{{}}\n\\maketitle\n\\tableofcontents\n\\newpage\n",
+            latex_escape(title)
+        ));
+    }
+    for art in articles {
+        out.push_str(&latex_article(art, readings, notes, numbered));
+    }
+    out.push_str("\\end{document}\n");
+    out
+}
+
+fn typst_escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if "\\/[]#*_`$<>@~=-+\"'".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn typst_pieces(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Char(Annotated {
+                ch,
+                reading: Some(r),
+            }) if poem::is_text(*ch) => format!("#ruby[{}][{}]", typst_escape(r), ch),
+            Piece::Char(a) => typst_escape(&a.ch.to_string()),
+            Piece::Note(n) => format!("#footnote[{}]", typst_escape(n)),
+            Piece::Break => " \\\n".to_string(),
+        })
+        .collect()
+}
+
+fn typst_article(art: &Article, readings: &ReadingOverrides, notes: &Notes) -> String {
+    let mut out = format!(
+        "= {}\n#align(center)[{}]\n\n",
+        typst_escape(&art.title),
+        typst_escape(&art.byline())
+    );
+    let (rows, unplaced) = rows(art, readings);
+    let parallel = rows.iter().any(|r| !r.translation.is_empty());
+    if parallel {
+        out.push_str("#grid(\n  columns: (1fr, 1fr),\n  column-gutter: 2em,\n  row-gutter: 1em,\n");
+        for row in rows {
+            out.push_str(&format!(
+                "  [{}],\n  [{}],\n",
+                typst_pieces(&row.original),
+                paragraphs(&row.translation)
+                    .iter()
+                    .map(|p| typst_escape(p))
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            ));
+        }
+        out.push_str(")\n\n");
+    } else {
+        for row in rows {
+            out.push_str(&format!("{}\n\n", typst_pieces(&row.original)));
+        }
+    }
+    if !unplaced.is_empty() {
+        out.push_str("== 注释\n");
+        for entry in unplaced {
+            out.push_str(&format!("- {}\n", typst_escape(&note(&entry))));
+        }
+        out.push('\n');
+    }
+    if let Some(shangxi) = art.shangxi.as_deref().filter(|s| !s.trim().is_empty()) {
+        out.push_str("== 赏析\n");
+        for p in paragraphs(shangxi) {
+            out.push_str(&format!("{}\n\n", typst_escape(p)));
+        }
+    }
+    let own = notes.get(&art.id);
+    if !own.is_empty() {
+        let poem = art.poem();
+        out.push_str("== 笔记\n");
+        for n in own {
+            out.push_str(&format!(
+                "- {}：{}\n",
+                typst_escape(&n.label(&poem)),
+                typst_escape(&n.text)
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Typst 文档, 排版方式同 LaTeX
+pub fn typst(
+    articles: &[Article],
+    title: &str,
+    readings: &ReadingOverrides,
+    notes: &Notes,
+) -> String {
+    let mut out = TYPST_PREAMBLE.to_string();
+    if articles.len() > 1 {
+        out.push_str(&format!(
+            "#align(center, text(size: 2em)[{}])\n#outline(title: \"目录\")\n#pagebreak()\n\n",
+            typst_escape(title)
+        ));
+    }
+    for art in articles {
+        out.push_str(&typst_article(art, readings, notes));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::jingyesi;
+
+    fn article() -> Article {
+        Article {
+            remark: Some("⑴床：井栏。\n⑵疑：好像。\n⑶秋霜：秋天的霜。".to_string()),
+            translation: Some("井边洒满了月光，好像地上的霜。\n抬头望月，低头思乡。".to_string()),
+            ..jingyesi()
+        }
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut readings = ReadingOverrides::default();
+        readings.set("1", 0, "chuang2");
+        let (rows, unplaced) = rows(&article(), &readings);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].translation, "抬头望月，低头思乡。");
+        assert_eq!(
+            rows[0].original[0],
+            Piece::Char(Annotated {
+                ch: '床',
+                reading: Some("chuang2".to_string()),
+            })
+        );
+        assert_eq!(rows[0].original[1], Piece::Note("床：井栏。".to_string()));
+        // 第一联的句末标点留在本行
+        assert!(matches!(
+            rows[0].original.last(),
+            Some(Piece::Char(Annotated { ch: '。', .. }))
+        ));
+        assert!(!rows[1].original.contains(&Piece::Break));
+        assert_eq!(unplaced.len(), 1);
+        assert_eq!(unplaced[0].term, "秋霜");
+
+        // 译文无法对齐时全文一行, 保留换行
+        let art = Article {
+            translation: None,
+            ..article()
+        };
+        let (rows, _) = super::rows(&art, &ReadingOverrides::default());
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].original.contains(&Piece::Break));
+        assert!(rows[0].translation.is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let readings = ReadingOverrides::default();
+        let mut notes = Notes::default();
+        notes.add("1", None, "五言绝句");
+        let tex = latex(&[article()], "合集", &readings, &notes);
+        assert!(tex.contains(r"\section*{静夜思}"));
+        assert!(tex.contains(r"\ruby{床}{chuáng}\footnote{床：井栏。}\ruby{前}{qián}"));
+        assert!(tex.contains("\\switchcolumn\n井边洒满了月光，好像地上的霜。\n\\switchcolumn*\n"));
+        assert!(tex.contains(r"\item 秋霜：秋天的霜。"));
+        assert!(!tex.contains(r"\tableofcontents"));
+        assert!(tex.contains("\\subsection*{笔记}\n\\begin{itemize}\n\\item 全文：五言绝句\n"));
+        assert_eq!(latex_escape("50% & $x_1$"), r"50\% \& \$x\_1\$");
+
+        let typ = typst(
+            &[article(), article()],
+            "合集",
+            &readings,
+            &Notes::default(),
+        );
+        assert!(typ.contains("#outline(title: \"目录\")"));
+        assert!(typ.contains("= 静夜思\n#align(center)[〔唐代〕李白]"));
+        assert!(typ.contains("  [#ruby[chuáng][床]#footnote[床：井栏。]#ruby[qián][前]"));
+        assert!(typ.contains("== 注释\n- 秋霜：秋天的霜。"));
+        assert!(!typ.contains("== 笔记"));
+        assert_eq!(typst_escape("#a[*]"), r"\#a\[\*\]");
+    }
+}
@@ -1,23 +1,45 @@
+use cursive::theme::{BaseColor, Color, Effect, Style};
 use cursive::traits::*;
+use cursive::utils::markup::StyledString;
 use cursive::view::Scrollable;
 use cursive::views::{
-    Button, Dialog, DummyView, EditView, LayerPosition, LinearLayout, ListView, Panel, RadioGroup,
-    SelectView, StackView, TextView,
+    Button, Checkbox, Dialog, DummyView, EditView, LayerPosition, LinearLayout, ListView,
+    NamedView, OnEventView, Panel, RadioButton, RadioGroup, ScrollView, SelectView, StackView,
+    TextArea, TextView,
 };
 use cursive::{Cursive, CursiveExt};
 use rand::Rng;
 use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+use crate::align;
+use crate::anki;
 use crate::api::*;
-
-const CN_PUNCTIONS: [char; 74] = [
-    '！', '？', '｡', '＂', '＃', '＄', '％', '＆', '＇', '（', '）', '＊', '＋', '，', '－', '／',
-    '：', '；', '＜', '＝', '＞', '＠', '［', '＼', '］', '＾', '＿', '｀', '｛', '｜', '｝', '～',
-    '｟', '｠', '｢', '｣', '､', '、', '〃', '》', '「', '」', '『', '』', '【', '】', '〔', '〕',
-    '〖', '〗', '〘', '〙', '〚', '〛', '〜', '〝', '〞', '〟', '〰', '〾', '〿', '–', '—', '‘',
-    '’', '‛', '“', '”', '„', '‟', '…', '‧', '﹏', '.',
-];
+use crate::collection::{Collection, Library, Progress, Status};
+use crate::concordance;
+use crate::corpus::Corpus;
+use crate::discover::Discoverer;
+use crate::export;
+use crate::favorites::Favorites;
+use crate::feihua::{self, Game};
+use crate::fortune;
+use crate::index::{self, Index};
+use crate::note::Notes;
+use crate::poem::{self, Form, CN_PUNCTIONS};
+use crate::practice;
+use crate::prosody::{self, RhymeTable, Tone};
+use crate::reading::{self, PinyinMode, ReadingOverrides};
+use crate::related::Recommender;
+use crate::remark;
+use crate::script::{Converter, Script};
+use crate::search::{self, LinePattern};
+use crate::store::data_dir;
+use crate::suggest::{Kind, Suggester, Suggestion};
+use crate::tag::Tags;
+use crate::theme::Lexicon;
+use crate::vertical::VerticalText;
 
 #[derive(Default, Debug, Clone)]
 struct MSG {
@@ -44,6 +66,27 @@ struct RenderData {
     writer_resp: Rc<RefCell<Option<WriterListResp>>>,
     index: Rc<Cell<usize>>,
     sview_vec: RefCell<Vec<String>>,
+    interleaved: Cell<bool>,
+    pinyin_mode: Cell<PinyinMode>,
+    tone_overlay: Cell<bool>,
+    /// 正文中选中的注解词语, 为 `remark::locate` 结果的下标
+    term_cursor: Cell<Option<usize>>,
+    rhymes: Rc<RhymeTable>,
+    readings: Rc<RefCell<ReadingOverrides>>,
+    corpus: Rc<RefCell<Corpus>>,
+    feihua: Rc<RefCell<Option<Game>>>,
+    /// 全文索引, 首次检索时建立
+    search_index: Rc<RefCell<Option<Index>>>,
+    script: Cell<Script>,
+    converter: Rc<Converter>,
+    discoverer: Rc<RefCell<Discoverer>>,
+    favorites: Rc<RefCell<Favorites>>,
+    library: Rc<RefCell<Library>>,
+    progress: Rc<RefCell<Progress>>,
+    notes: Rc<RefCell<Notes>>,
+    tags: Rc<RefCell<Tags>>,
+    lexicon: Rc<Lexicon>,
+    recommender: Rc<RefCell<Recommender>>,
 }
 
 impl RenderData {
@@ -53,7 +96,26 @@ impl RenderData {
             sen_resp: Rc::new(RefCell::new(None)),
             writer_resp: Rc::new(RefCell::new(None)),
             index: Rc::new(Cell::new(0)),
-            sview_vec: RefCell::new("r t s c".split(' ').map(|i| i.to_string()).collect()),
+            sview_vec: RefCell::new("v p r t s n c".split(' ').map(|i| i.to_string()).collect()),
+            interleaved: Cell::new(false),
+            pinyin_mode: Cell::new(PinyinMode::Off),
+            tone_overlay: Cell::new(false),
+            term_cursor: Cell::new(None),
+            rhymes: Rc::new(RhymeTable::bundled()),
+            readings: Rc::new(RefCell::new(ReadingOverrides::load())),
+            corpus: Rc::new(RefCell::new(Corpus::load())),
+            feihua: Rc::new(RefCell::new(None)),
+            search_index: Rc::new(RefCell::new(None)),
+            script: Cell::new(Script::load()),
+            converter: Rc::new(Converter::bundled()),
+            discoverer: Rc::new(RefCell::new(Discoverer::new(None))),
+            favorites: Rc::new(RefCell::new(Favorites::load())),
+            library: Rc::new(RefCell::new(Library::load())),
+            progress: Rc::new(RefCell::new(Progress::load())),
+            notes: Rc::new(RefCell::new(Notes::load())),
+            tags: Rc::new(RefCell::new(Tags::load())),
+            lexicon: Rc::new(Lexicon::bundled()),
+            recommender: Rc::new(RefCell::new(Recommender::default())),
         }
     }
 
@@ -74,34 +136,242 @@ impl RenderData {
     fn get_artitle(&self, idx: usize) -> Option<Article> {
         if self.art_resp.borrow().is_some() {
             match self.art_resp.borrow().as_ref().unwrap().data.get(idx) {
-                Some(art) => art.show().ok(),
+                Some(art) => self.show(art).ok(),
                 None => None,
             }
         } else {
             None
         }
     }
+
+    /// 在本地语料中按韵部, 平仄, 诗句或全文检索, 或浏览选集, 检索笔记, 按标签或题材浏览, 列出相关作品
+    fn search_local(
+        &self,
+        method: Method,
+        page: usize,
+    ) -> Result<ArtListResp, Box<dyn std::error::Error>> {
+        if let Method::Collection(name) = &method {
+            return self.collection_page(name, page);
+        }
+        if let Method::Tag(tag) = &method {
+            return Ok(self.tags.borrow().page(tag, page));
+        }
+        if let Method::Note(query) = &method {
+            let found = self
+                .notes
+                .borrow()
+                .search(query)
+                .into_iter()
+                .map(|(id, note)| {
+                    let title = self
+                        .corpus
+                        .borrow()
+                        .get(id)
+                        .map(|art| art.title.clone())
+                        .unwrap_or_default();
+                    ArticleSimple {
+                        id: id.to_string(),
+                        title,
+                        snippet: Some(note.text.clone()),
+                    }
+                })
+                .collect();
+            return Ok(ArtListResp::paginate(found, page, method));
+        }
+        // 韵部名统一为完整名称, 如 "尤" 记为 "下平十一尤"
+        let method = match method {
+            Method::Rhyme(name) => Method::Rhyme(self.rhymes.find_group(&name)?.to_string()),
+            method => method,
+        };
+        let corpus = self.corpus.borrow();
+        let found = match &method {
+            Method::Rhyme(group) => corpus
+                .articles()
+                .iter()
+                .filter(|art| prosody::rhymes_with(&art.poem(), &self.rhymes, group))
+                .collect::<Vec<_>>(),
+            Method::Tone(pattern) => {
+                let pattern = prosody::parse_pattern(pattern)?;
+                corpus
+                    .articles()
+                    .iter()
+                    .filter(|art| prosody::matches_pattern(&art.poem(), &self.rhymes, &pattern))
+                    .collect::<Vec<_>>()
+            }
+            Method::Line(pattern) => {
+                let pattern = LinePattern::parse(pattern)?;
+                return Ok(ArtListResp::paginate(
+                    search::search_lines(&corpus, &pattern),
+                    page,
+                    method,
+                ));
+            }
+            Method::Related(id) => {
+                let found = self
+                    .recommender
+                    .borrow_mut()
+                    .related(&corpus, &self.tags.borrow(), id, LOCAL_PAGE_SIZE)
+                    .into_iter()
+                    .map(|r| ArticleSimple {
+                        id: r.id,
+                        title: r.title,
+                        snippet: Some(r.reasons.join(" · ")),
+                    })
+                    .collect();
+                return Ok(ArtListResp::paginate(found, page, method));
+            }
+            Method::Theme(query) => {
+                let found = self.lexicon.search(&corpus, query)?;
+                return Ok(ArtListResp::paginate(found, page, method));
+            }
+            Method::FullText(query) => {
+                let terms = index::parse_query(query);
+                let mut index = self.search_index.borrow_mut();
+                let index = index.get_or_insert_with(|| Index::build(&corpus));
+                let found = index
+                    .search(&corpus, &terms)
+                    .iter()
+                    .map(|hit| hit.to_simple())
+                    .collect();
+                return Ok(ArtListResp::paginate(found, page, method));
+            }
+            _ => vec![],
+        };
+        let found = found.into_iter().map(Article::to_simple).collect();
+        Ok(ArtListResp::paginate(found, page, method))
+    }
+
+    /// 选集的一页, 本地没有的文章联网按标题查找
+    fn collection_page(
+        &self,
+        name: &str,
+        page: usize,
+    ) -> Result<ArtListResp, Box<dyn std::error::Error>> {
+        let collection = self.library.borrow().get(name).ok_or(APIError::NotFound)?;
+        let mut progress = self.progress.borrow_mut();
+        let resp = progress.page(&collection, &mut self.corpus.borrow_mut(), page, |title| {
+            Ok(ArtListResp::list_by_keyword(1, title.to_string())?.data)
+        })?;
+        if let Err(e) = progress.save() {
+            log::warn!("failed to save progress: {}", e);
+        }
+        Ok(resp)
+    }
+
+    /// 按检索方式获取指定页, 本地检索走文集, 离线时按页浏览改为浏览文集
+    fn list(
+        &self,
+        method: &Method,
+        page: usize,
+    ) -> Result<ArtListResp, Box<dyn std::error::Error>> {
+        match method {
+            Method::Page => ArtListResp::list_by_page(page).or_else(|e| {
+                let corpus = self.corpus.borrow();
+                if corpus.articles().is_empty() {
+                    return Err(e);
+                }
+                let found = corpus.articles().iter().map(Article::to_simple).collect();
+                Ok(ArtListResp::paginate(found, page, Method::Page))
+            }),
+            // 离线时改用本地全文索引
+            Method::Keyword(keyword) => ArtListResp::list(method, page)
+                .or_else(|_| self.search_local(Method::FullText(keyword.clone()), page)),
+            Method::Writer(_) | Method::Dynasty(_) => ArtListResp::list(method, page),
+            _ => self.search_local(method.clone(), page),
+        }
+    }
+
+    /// 按显示设置转换简繁
+    fn display(&self, text: &str) -> String {
+        self.converter.convert(text, self.script.get())
+    }
+
+    /// 按显示设置转换整篇文章, 用于导出
+    fn display_article(&self, art: &Article) -> Article {
+        let convert = |text: &Option<String>| text.as_deref().map(|t| self.display(t));
+        Article {
+            title: self.display(&art.title),
+            writer: self.display(&art.writer),
+            dynasty: self.display(&art.dynasty),
+            content: self.display(&art.content),
+            remark: convert(&art.remark),
+            translation: convert(&art.translation),
+            shangxi: convert(&art.shangxi),
+            ..art.clone()
+        }
+    }
+
+    fn display_styled(&self, text: &StyledString) -> StyledString {
+        let mut out = StyledString::new();
+        for span in text.spans() {
+            out.append_styled(self.display(span.content), *span.attr);
+        }
+        out
+    }
+
+    fn show(&self, art: &ArticleSimple) -> Result<Article, Box<dyn std::error::Error>> {
+        // 选集中未能找到的条目没有 id
+        if art.id.is_empty() {
+            return Err(format!("《{}》未找到", art.title).into());
+        }
+        let article = self.corpus.borrow_mut().fetch(art)?;
+        if let Some(index) = self.search_index.borrow_mut().as_mut() {
+            index.add(&article);
+        }
+        Ok(article)
+    }
 }
 
-pub fn render_ui() {
+pub fn render_ui(seed: Option<u64>) {
     let mut siv = Cursive::default();
-    siv.set_user_data(RenderData::new_with_rc());
+    let data = RenderData::new_with_rc();
+    if seed.is_some() {
+        *data.discoverer.borrow_mut() = Discoverer::new(seed);
+    }
+    siv.set_user_data(data);
     siv.add_fullscreen_layer(render());
     siv.run();
 }
 
 fn render() -> impl View {
-    LinearLayout::vertical()
-        .child(
-            LinearLayout::horizontal()
-                .child(build_info_panel())
-                .child(build_stack_view()),
-        )
-        .child(build_bottom())
+    OnEventView::new(
+        LinearLayout::vertical()
+            .child(
+                LinearLayout::horizontal()
+                    .child(build_info_panel())
+                    .child(build_stack_view()),
+            )
+            .child(build_bottom()),
+    )
+    .on_event(']', |s| move_term(s, 1))
+    .on_event('[', |s| move_term(s, -1))
 }
 
 fn build_stack_view() -> impl View {
     let mut stack_view = StackView::new();
+    stack_view.add_fullscreen_layer(
+        Panel::new(VerticalText::new().with_name("vertical_text"))
+            .title("竖排")
+            .full_screen(),
+    );
+    stack_view.add_fullscreen_layer(
+        Panel::new(
+            LinearLayout::vertical()
+                .child(
+                    LinearLayout::horizontal()
+                        .child(DummyView.full_width())
+                        .child(Button::new_raw("[ 并列 | 交错 ]", toggle_parallel_mode)),
+                )
+                .child(
+                    LinearLayout::vertical()
+                        .with_name("parallel_rows")
+                        .scrollable()
+                        .scroll_y(true),
+                ),
+        )
+        .title("对照")
+        .full_screen(),
+    );
     stack_view.add_fullscreen_layer(
         Panel::new(
             TextView::empty()
@@ -132,14 +402,34 @@ fn build_stack_view() -> impl View {
         .title("赏析")
         .full_screen(),
     );
+    stack_view.add_fullscreen_layer(
+        Panel::new(
+            LinearLayout::vertical()
+                .child(
+                    LinearLayout::horizontal()
+                        .child(DummyView.full_width())
+                        .child(Button::new_raw("[ 添加 ]", render_note_form))
+                        .child(Button::new_raw("[ 删除 ]", render_note_menu)),
+                )
+                .child(
+                    TextView::empty()
+                        .with_name("notes_text")
+                        .scrollable()
+                        .scroll_y(true),
+                ),
+        )
+        .title("笔记")
+        .full_screen(),
+    );
     stack_view.add_fullscreen_layer(
         Panel::new(
             TextView::empty()
                 .with_name("content_text")
                 .scrollable()
-                .scroll_y(true),
+                .scroll_y(true)
+                .with_name("content_scroll"),
         )
-        .title("正文")
+        .title("正文 ( [ ] 查看注解 )")
         .full_screen(),
     );
     stack_view.with_name("stack_view").min_width(80)
@@ -150,6 +440,9 @@ fn build_info_panel() -> impl View {
         ListView::new()
             .child("标题:", TextView::new("-").with_name("title"))
             .child("作者:", TextView::new("-").with_name("writer"))
+            .child("体裁:", TextView::new("-").with_name("form"))
+            .child("标签:", TextView::new("-").with_name("tags"))
+            .child("题材:", TextView::new("-").with_name("themes"))
             .child(
                 "正文:",
                 LinearLayout::horizontal()
@@ -190,10 +483,41 @@ fn build_info_panel() -> impl View {
                     )
                     .child(DummyView.full_width()),
             )
+            .child(
+                "笔记:",
+                LinearLayout::horizontal()
+                    .child(
+                        Button::new_raw("-", move |s| visible_view(s, "n"))
+                            .disabled()
+                            .with_name("notes_btn"),
+                    )
+                    .child(DummyView.full_width()),
+            )
+            .child(
+                "竖排:",
+                LinearLayout::horizontal()
+                    .child(
+                        Button::new_raw("-", move |s| visible_view(s, "v"))
+                            .disabled()
+                            .with_name("vertical_btn"),
+                    )
+                    .child(DummyView.full_width()),
+            )
+            .child(
+                "对照:",
+                LinearLayout::horizontal()
+                    .child(
+                        Button::new_raw("-", move |s| visible_view(s, "p"))
+                            .disabled()
+                            .with_name("parallel_btn"),
+                    )
+                    .child(DummyView.full_width()),
+            )
             .child("总数:", TextView::new("-").with_name("total"))
             .child("页数:", TextView::new("-").with_name("page_page"))
             .child("索引:", TextView::new("-").with_name("index"))
-            .child("方法:", TextView::new("-").with_name("method")),
+            .child("方法:", TextView::new("-").with_name("method"))
+            .child("匹配:", TextView::new("-").with_name("snippet")),
     )
     .title("信息")
     .min_width(20)
@@ -205,6 +529,7 @@ fn build_bottom() -> impl View {
         LinearLayout::horizontal()
             .child(DummyView.full_width())
             .child(Button::new_raw("[ 搜索 ]", render_form).with_name("search_button"))
+            .child(Button::new_raw("[ 随机 ]", random_article))
             .child(Button::new_raw("[ 背诵 ]", move |s| {
                 let mut select = SelectView::new().autojump();
                 select.add_item("无", MaskLevel::Empty);
@@ -217,6 +542,7 @@ fn build_bottom() -> impl View {
                     s.pop_layer();
                 }))
             }))
+            .child(Button::new_raw("[ 更多 ]", render_more_menu))
             .child(TextView::new(" || "))
             .child(Button::new_raw("[上一个]", prev_item))
             .child(TextView::new("|"))
@@ -230,7 +556,37 @@ fn build_bottom() -> impl View {
     .fixed_height(3)
 }
 
+/// 底栏放不下的功能
+fn render_more_menu(s: &mut Cursive) {
+    let select = SelectView::<fn(&mut Cursive)>::new()
+        .item("飞花令", render_feihua_form)
+        .item("拼音", render_pinyin_menu)
+        .item("格律", render_prosody_menu)
+        .item("简繁", render_script_menu)
+        .item("收藏", toggle_favorite)
+        .item("选集", render_collection_menu)
+        .item("标签", render_tag_menu)
+        .item("题材", render_theme_menu)
+        .item("意象", render_concordance_form)
+        .item("相关", render_related)
+        .item("导出", render_export_form)
+        .item("字帖", render_practice_form)
+        .on_submit(|s, action: &fn(&mut Cursive)| {
+            s.pop_layer();
+            action(s);
+        });
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title("更多")
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
 fn render_form(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let suggester = Rc::new(Suggester::build(&data.corpus.borrow()));
     let mut method_group: RadioGroup<Method> = RadioGroup::new();
     method_group.set_on_change(|s: &mut Cursive, v| match v {
         Method::Page => s
@@ -240,6 +596,7 @@ fn render_form(s: &mut Cursive) {
             .call_on_name("val", |view: &mut EditView| view.enable())
             .unwrap(),
     });
+    let edit_suggester = suggester.clone();
     s.add_layer(
         Dialog::new()
             .title("输入搜索选项")
@@ -249,9 +606,28 @@ fn render_form(s: &mut Cursive) {
                         "方法",
                         LinearLayout::horizontal()
                             .child(method_group.button(Method::Page, "页数"))
-                            .child(method_group.button(Method::Writer(String::new()), "作者"))
-                            .child(method_group.button(Method::Keyword(String::new()), "关键字"))
-                            .child(method_group.button(Method::Dynasty(String::new()), "朝代")),
+                            .child(
+                                method_group
+                                    .button(Method::Writer(String::new()), "作者")
+                                    .with_name("method_writer"),
+                            )
+                            .child(
+                                method_group
+                                    .button(Method::Keyword(String::new()), "关键字")
+                                    .with_name("method_keyword"),
+                            )
+                            .child(
+                                method_group
+                                    .button(Method::Dynasty(String::new()), "朝代")
+                                    .with_name("method_dynasty"),
+                            )
+                            .child(method_group.button(Method::Rhyme(String::new()), "韵部"))
+                            .child(method_group.button(Method::Tone(String::new()), "平仄"))
+                            .child(method_group.button(Method::Line(String::new()), "诗句"))
+                            .child(method_group.button(Method::FullText(String::new()), "全文"))
+                            .child(method_group.button(Method::Note(String::new()), "笔记"))
+                            .child(method_group.button(Method::Tag(String::new()), "标签"))
+                            .child(method_group.button(Method::Theme(String::new()), "题材")),
                     )
                     .child(
                         "页数",
@@ -262,17 +638,61 @@ fn render_form(s: &mut Cursive) {
                     )
                     .child(
                         "输入",
-                        EditView::new().disabled().with_name("val").fixed_width(10),
+                        EditView::new()
+                            .disabled()
+                            .on_edit(move |s, text, _| suggest_input(s, &edit_suggester, text))
+                            .with_name("val")
+                            .fixed_width(20),
+                    )
+                    .child(
+                        "候选",
+                        SelectView::<Suggestion>::new()
+                            .on_submit(pick_suggestion)
+                            .with_name("suggestions")
+                            .fixed_height(5),
                     ),
             )
-            .button("提交", move |s| on_submit(s, &method_group))
+            .button("提交", move |s| on_submit(s, &method_group, &suggester))
             .button("关闭", |s| {
                 s.pop_layer();
             }),
     )
 }
 
-fn on_submit(s: &mut Cursive, m_group: &RadioGroup<Method>) {
+/// 根据输入刷新候选项
+fn suggest_input(s: &mut Cursive, suggester: &Suggester, text: &str) {
+    let data = RenderData::get_current_data(s);
+    let items = suggester.suggest(&data.converter.to_simplified(text), 5);
+    s.call_on_name("suggestions", |view: &mut SelectView<Suggestion>| {
+        view.clear();
+        for item in items {
+            let label = format!("{} ({})", data.display(&item.text), item.kind.name());
+            view.add_item(label, item);
+        }
+    })
+    .unwrap();
+}
+
+/// 选中候选项后填入输入框, 并切换到对应的检索方法
+fn pick_suggestion(s: &mut Cursive, item: &Suggestion) {
+    let text = item.text.clone();
+    s.call_on_name("val", |view: &mut EditView| view.set_content(text))
+        .unwrap();
+    let name = match item.kind {
+        Kind::Title => "method_keyword",
+        Kind::Writer => "method_writer",
+        Kind::Dynasty => "method_dynasty",
+    };
+    if let Some(result) = s.call_on_name(name, |view: &mut RadioButton<Method>| view.select()) {
+        result.process(s);
+    }
+    s.call_on_name("suggestions", |view: &mut SelectView<Suggestion>| {
+        view.clear()
+    })
+    .unwrap();
+}
+
+fn on_submit(s: &mut Cursive, m_group: &RadioGroup<Method>, suggester: &Suggester) {
     let data = RenderData::get_current_data(s);
     let method = m_group.selection();
     let page_raw = s
@@ -281,55 +701,61 @@ fn on_submit(s: &mut Cursive, m_group: &RadioGroup<Method>) {
     let val_raw = s
         .call_on_name("val", |view: &mut EditView| view.get_content())
         .unwrap();
+    // 繁体输入统一为简体, 拼音或首字母输入解析为文集中对应的名称
+    let val_raw = data.converter.to_simplified(&val_raw);
+    let kind = match method.as_ref() {
+        Method::Writer(_) => Some(Kind::Writer),
+        Method::Dynasty(_) => Some(Kind::Dynasty),
+        Method::Keyword(_) => Some(Kind::Title),
+        _ => None,
+    };
+    let val_raw = kind
+        .and_then(|kind| suggester.resolve(&val_raw, kind))
+        .unwrap_or(val_raw);
 
-    if let Ok(page) = page_raw.parse::<usize>() {
-        if page < 1 {
+    match page_raw.parse::<usize>() {
+        Ok(page) if page >= 1 => open_page(s, method.with_query(&val_raw), page),
+        _ => {
             s.add_layer(Dialog::info("请输入正整数( >= 1)"));
-            return;
         }
-        let resp = match method.as_ref() {
-            Method::Page => ArtListResp::list_by_page(page),
-            Method::Dynasty(_) => ArtListResp::list_by_dynasty(page, val_raw.to_string()),
-            Method::Writer(_) => ArtListResp::list_by_writer(page, val_raw.to_string()),
-            Method::Keyword(_) => ArtListResp::list_by_keyword(page, val_raw.to_string()),
-        };
+    }
+}
 
-        match resp {
-            Ok(resp) => match resp.data.get(0) {
-                Some(art) => match art.show() {
-                    Ok(article) => {
-                        let msg = MSG {
-                            article,
-                            page: resp.page,
-                            total: resp.total,
-                            page_size: resp.page_size,
-                            index: 0,
-                            method: method.as_ref().clone(),
-                        };
-                        *data.art_resp.borrow_mut() = Some(resp);
-                        data.index.set(0);
-                        if s.cb_sink()
-                            .clone()
-                            .send(Box::new(move |s| update(s, msg)))
-                            .is_err()
-                        {
-                            s.add_layer(Dialog::info("发送错误"));
-                        }
-                    }
-                    Err(_) => {
-                        s.add_layer(Dialog::info("内容获取错误"));
-                    }
-                },
-                None => {
-                    s.add_layer(Dialog::info("内容为空!"));
-                }
-            },
-            Err(_) => {
-                s.add_layer(Dialog::info("内容获取错误"));
-            }
+/// 在当前检索范围内随机显示一篇, 尚未检索时从全部文章中选取
+fn random_article(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let method = data
+        .art_resp
+        .borrow()
+        .as_ref()
+        .map(|resp| resp.method.clone())
+        .unwrap_or_default();
+    let picked = data
+        .discoverer
+        .borrow_mut()
+        .pick(|page| data.list(&method, page));
+    let (resp, idx) = match picked {
+        Ok(picked) => picked,
+        Err(e) => {
+            s.add_layer(Dialog::info(format!("内容获取错误: {}", e)));
+            return;
         }
-    } else {
-        s.add_layer(Dialog::info("请输入正整数( >= 1)"));
+    };
+    match data.show(&resp.data[idx]) {
+        Ok(article) => {
+            let msg = MSG {
+                article,
+                page: resp.page,
+                total: resp.total,
+                page_size: resp.page_size,
+                index: idx,
+                method: resp.method.clone(),
+            };
+            *data.art_resp.borrow_mut() = Some(resp);
+            data.index.set(idx);
+            update(s, msg);
+        }
+        Err(e) => s.add_layer(Dialog::info(format!("内容获取错误: {}", e))),
     }
 }
 
@@ -385,85 +811,30 @@ fn next_item(s: &mut Cursive) {
 
 fn prev_page(s: &mut Cursive) {
     let data = RenderData::get_current_data(s);
-    if data.art_resp.borrow().is_some() {
-        let art_resp = data.art_resp.borrow().clone().unwrap();
-        let new_resp = art_resp.prev_page();
-        match new_resp {
-            Ok(resp) => match resp.data.get(0) {
-                Some(art) => match art.show() {
-                    Ok(article) => {
-                        let msg = MSG {
-                            article,
-                            page: resp.page,
-                            total: resp.total,
-                            page_size: resp.page_size,
-                            index: 0,
-                            method: art_resp.method.clone(),
-                        };
-                        *data.art_resp.borrow_mut() = Some(resp);
-                        data.index.set(0);
-                        if s.cb_sink()
-                            .clone()
-                            .send(Box::new(move |s| update(s, msg)))
-                            .is_err()
-                        {
-                            s.add_layer(Dialog::info("发送错误"));
-                        }
-                    }
-                    Err(_) => {
-                        s.add_layer(Dialog::info("内容获取错误"));
-                    }
-                },
-                None => {
-                    s.add_layer(Dialog::info("内容为空!"));
-                }
-            },
-            Err(_) => {
-                s.add_layer(Dialog::info("内容获取错误"));
-            }
-        }
+    let page = data.art_resp.borrow().as_ref().map(|resp| resp.page);
+    if let Some(page) = page {
+        goto_page(s, page.max(2) - 1);
     }
 }
 
 fn next_page(s: &mut Cursive) {
     let data = RenderData::get_current_data(s);
-    if data.art_resp.borrow().is_some() {
-        let art_resp = data.art_resp.borrow().clone().unwrap();
-        let new_resp = art_resp.next_page();
-        match new_resp {
-            Ok(resp) => match resp.data.get(0) {
-                Some(art) => match art.show() {
-                    Ok(article) => {
-                        let msg = MSG {
-                            article,
-                            page: resp.page,
-                            total: resp.total,
-                            page_size: resp.page_size,
-                            index: 0,
-                            method: art_resp.method.clone(),
-                        };
-                        *data.art_resp.borrow_mut() = Some(resp);
-                        data.index.set(0);
-                        if s.cb_sink()
-                            .clone()
-                            .send(Box::new(move |s| update(s, msg)))
-                            .is_err()
-                        {
-                            s.add_layer(Dialog::info("发送错误"));
-                        }
-                    }
-                    Err(_) => {
-                        s.add_layer(Dialog::info("内容获取错误"));
-                    }
-                },
-                None => {
-                    s.add_layer(Dialog::info("内容为空!"));
-                }
-            },
-            Err(_) => {
-                s.add_layer(Dialog::info("内容获取错误"));
-            }
-        }
+    let page = data.art_resp.borrow().as_ref().map(|resp| resp.page);
+    if let Some(page) = page {
+        goto_page(s, page + 1);
+    }
+}
+
+/// 按当前的检索方式翻到指定页
+fn goto_page(s: &mut Cursive, page: usize) {
+    let data = RenderData::get_current_data(s);
+    let method = data
+        .art_resp
+        .borrow()
+        .as_ref()
+        .map(|resp| resp.method.clone());
+    if let Some(method) = method {
+        open_page(s, method, page);
     }
 }
 
@@ -480,18 +851,65 @@ fn update(s: &mut Cursive, msg: MSG) {
         .unwrap();
     }
 
+    fn render_available(s: &mut Cursive, id: &str, available: bool) {
+        s.call_on_name(id, |view: &mut Button| {
+            if available {
+                view.enable();
+                view.set_label_raw("[ √ ]");
+            } else {
+                view.disable();
+                view.set_label_raw("[ × ]");
+            }
+        })
+        .unwrap();
+    }
+
+    let data = RenderData::get_current_data(s);
     s.call_on_name("title", |view: &mut TextView| {
-        view.set_content(msg.article.title.to_string())
+        view.set_content(data.display(&msg.article.title))
     })
     .unwrap();
     s.call_on_name("writer", |view: &mut TextView| {
-        view.set_content(msg.article.writer.to_string())
+        view.set_content(data.display(&msg.article.writer))
+    })
+    .unwrap();
+    s.call_on_name("form", |view: &mut TextView| {
+        let poem = msg.article.poem();
+        let lines = poem.lines().count();
+        view.set_content(match poem.form {
+            Form::Ci | Form::Wen => {
+                format!("{} {}段{}句", poem.form.name(), poem.stanzas.len(), lines)
+            }
+            _ => format!(
+                "{} {}联{}句",
+                poem.form.name(),
+                poem.couplets().len(),
+                lines
+            ),
+        })
+    })
+    .unwrap();
+    render_tags(s, &msg.article.id);
+    let themes: Vec<String> = data
+        .lexicon
+        .classify(&msg.article)
+        .into_iter()
+        .map(|l| l.theme)
+        .collect();
+    s.call_on_name("themes", |view: &mut TextView| {
+        view.set_content(if themes.is_empty() {
+            "-".to_string()
+        } else {
+            themes.join("、")
+        })
     })
     .unwrap();
     render_label(s, "content_btn", &msg);
     render_label(s, "remark_btn", &msg);
     render_label(s, "translation_btn", &msg);
     render_label(s, "shangxi_btn", &msg);
+    render_available(s, "parallel_btn", msg.article.translation.is_some());
+    render_available(s, "vertical_btn", !msg.article.content.trim().is_empty());
     s.call_on_name("total", |view: &mut TextView| {
         view.set_content(msg.total.to_string())
     })
@@ -505,37 +923,64 @@ fn update(s: &mut Cursive, msg: MSG) {
     })
     .unwrap();
     s.call_on_name("method", |view: &mut TextView| {
-        let content = match msg.method.clone() {
-            Method::Page => "总览".to_string(),
-            Method::Dynasty(dynasty) => format!("朝代 - {}", dynasty),
-            Method::Writer(writer) => format!("作者 - {}", writer),
-            Method::Keyword(keyword) => format!("关键字 - {}", keyword),
-        };
-        view.set_content(content)
+        view.set_content(msg.method.label())
     })
     .unwrap();
+    let snippet = data
+        .art_resp
+        .borrow()
+        .as_ref()
+        .and_then(|resp| resp.data.get(msg.index))
+        .and_then(|art| art.snippet.as_deref().map(|s| data.display(s)))
+        .unwrap_or_else(|| "-".to_string());
+    s.call_on_name("snippet", |view: &mut TextView| view.set_content(snippet))
+        .unwrap();
 
-    s.call_on_name("content_text", |view: &mut TextView| {
-        view.set_content(msg.article.content.to_string())
-    })
-    .unwrap();
+    data.term_cursor.set(None);
+    render_content(s, &msg.article);
+    render_parallel(s, &msg.article);
+    render_notes(s, &msg.article);
 
     s.call_on_name("remark_text", |view: &mut TextView| {
-        view.set_content(msg.article.remark.clone().unwrap())
+        let entries = msg.article.remarks();
+        if entries.is_empty() {
+            view.set_content(
+                data.display(or_placeholder(msg.article.remark.as_deref(), "暂无注释")),
+            )
+        } else {
+            let mut text = StyledString::new();
+            for entry in entries.iter() {
+                text.append_plain(format!("{}. ", entry.index));
+                text.append_styled(entry.term.as_str(), Effect::Bold);
+                text.append_plain(format!("：{}\n", entry.explanation));
+            }
+            view.set_content(data.display_styled(&text))
+        }
     })
     .unwrap();
 
     s.call_on_name("translation_text", |view: &mut TextView| {
-        view.set_content(msg.article.translation.clone().unwrap())
+        view.set_content(data.display(or_placeholder(
+            msg.article.translation.as_deref(),
+            "暂无译文",
+        )))
     })
     .unwrap();
 
     s.call_on_name("shangxi_text", |view: &mut TextView| {
-        view.set_content(msg.article.shangxi.clone().unwrap())
+        view.set_content(data.display(or_placeholder(msg.article.shangxi.as_deref(), "暂无赏析")))
     })
     .unwrap();
 }
 
+/// 文章缺少该项或为空时显示提示
+fn or_placeholder<'a>(text: Option<&'a str>, placeholder: &'a str) -> &'a str {
+    match text {
+        Some(text) if !text.trim().is_empty() => text,
+        _ => placeholder,
+    }
+}
+
 fn visible_view(s: &mut Cursive, id: &str) {
     let data = RenderData::get_current_data(s);
     s.call_on_name("stack_view", |view: &mut StackView| {
@@ -564,6 +1009,10 @@ fn mask_content(s: &mut Cursive, level: &MaskLevel) {
     };
     let art = data.get_artitle(data.index.get());
     if let Some(art) = art {
+        if level == 0.0 {
+            render_content(s, &art);
+            return;
+        }
         let masked_content: String = art
             .content
             .chars()
@@ -579,9 +1028,1707 @@ fn mask_content(s: &mut Cursive, level: &MaskLevel) {
                 }
             })
             .collect();
+        let masked_content = data.display(&masked_content);
+        s.call_on_name("vertical_text", |view: &mut VerticalText| {
+            view.set_content(masked_content.clone())
+        })
+        .unwrap();
         s.call_on_name("content_text", |view: &mut TextView| {
             view.set_content(masked_content)
         })
         .unwrap();
     }
 }
+
+fn render_feihua_form(s: &mut Cursive) {
+    let mut mode_group: RadioGroup<feihua::Mode> = RadioGroup::new();
+    s.add_layer(
+        Dialog::new()
+            .title("飞花令")
+            .content(
+                ListView::new()
+                    .child(
+                        "令字",
+                        EditView::new()
+                            .max_content_width(1)
+                            .with_name("feihua_keyword")
+                            .fixed_width(4),
+                    )
+                    .child(
+                        "模式",
+                        LinearLayout::horizontal()
+                            .child(mode_group.button(feihua::Mode::Solo, "人机"))
+                            .child(mode_group.button(feihua::Mode::HotSeat, "双人")),
+                    ),
+            )
+            .button("开始", move |s| start_feihua(s, &mode_group))
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn start_feihua(s: &mut Cursive, m_group: &RadioGroup<feihua::Mode>) {
+    let data = RenderData::get_current_data(s);
+    let keyword = s
+        .call_on_name("feihua_keyword", |view: &mut EditView| view.get_content())
+        .unwrap();
+    let keyword = match keyword.chars().next() {
+        Some(c) if !c.is_whitespace() => c,
+        _ => {
+            s.add_layer(Dialog::info("请输入一个令字"));
+            return;
+        }
+    };
+    let game = Game::new(&data.corpus.borrow(), keyword, *m_group.selection());
+    if game.candidates() == 0 {
+        s.add_layer(Dialog::info(format!(
+            "本地文集中没有含「{}」的诗句, 请先浏览更多文章",
+            keyword
+        )));
+        return;
+    }
+    let title = format!("飞花令 - {} ({})", keyword, game.candidates());
+    *data.feihua.borrow_mut() = Some(game);
+    s.pop_layer();
+    s.add_layer(
+        Dialog::new()
+            .title(title)
+            .content(
+                LinearLayout::vertical()
+                    .child(
+                        SelectView::<feihua::Verse>::new()
+                            .on_submit(show_verse_source)
+                            .with_name("feihua_history")
+                            .scrollable()
+                            .min_height(10)
+                            .min_width(50),
+                    )
+                    .child(TextView::new("").with_name("feihua_status"))
+                    .child(
+                        EditView::new()
+                            .on_submit(play_feihua)
+                            .with_name("feihua_input")
+                            .full_width(),
+                    ),
+            )
+            .button("出句", |s| {
+                let text = s
+                    .call_on_name("feihua_input", |view: &mut EditView| view.get_content())
+                    .unwrap();
+                play_feihua(s, &text);
+            })
+            .button("认输", |s| {
+                let data = RenderData::get_current_data(s);
+                let winner = data
+                    .feihua
+                    .borrow()
+                    .as_ref()
+                    .map(|game| game.turn.other().name(game.mode));
+                *data.feihua.borrow_mut() = None;
+                s.pop_layer();
+                if let Some(winner) = winner {
+                    s.add_layer(Dialog::info(format!("{} 获胜!", winner)));
+                }
+            })
+            .button("关闭", |s| {
+                RenderData::get_current_data(s).feihua.borrow_mut().take();
+                s.pop_layer();
+            }),
+    );
+    update_feihua(s);
+}
+
+fn play_feihua(s: &mut Cursive, text: &str) {
+    let data = RenderData::get_current_data(s);
+    let result = match data.feihua.borrow_mut().as_mut() {
+        Some(game) => game.play(text),
+        None => return,
+    };
+    if let Err(e) = result {
+        s.add_layer(Dialog::info(e.to_string()));
+        return;
+    }
+    s.call_on_name("feihua_input", |view: &mut EditView| view.set_content(""));
+    let computer_lost = match data.feihua.borrow_mut().as_mut() {
+        Some(game) if game.mode == feihua::Mode::Solo => {
+            game.computer_play(&mut rand::thread_rng()).is_none()
+        }
+        _ => false,
+    };
+    update_feihua(s);
+    if computer_lost {
+        *data.feihua.borrow_mut() = None;
+        s.add_layer(Dialog::info("电脑无句可接, 你赢了!"));
+    }
+}
+
+fn update_feihua(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let game = data.feihua.borrow();
+    let game = match game.as_ref() {
+        Some(game) => game,
+        None => return,
+    };
+    s.call_on_name("feihua_history", |view: &mut SelectView<feihua::Verse>| {
+        view.clear();
+        for (player, verse) in game.history.iter() {
+            view.add_item(
+                format!(
+                    "{}: {}  —— {}《{}》",
+                    player.name(game.mode),
+                    verse.line,
+                    verse.writer,
+                    verse.title
+                ),
+                verse.clone(),
+            );
+        }
+        if !view.is_empty() {
+            view.set_selection(view.len() - 1);
+        }
+    });
+    s.call_on_name("feihua_status", |view: &mut TextView| {
+        view.set_content(format!(
+            "第 {} 轮, 请 {} 出含「{}」的诗句",
+            game.history.len() + 1,
+            game.turn.name(game.mode),
+            game.keyword
+        ))
+    });
+}
+
+fn show_verse_source(s: &mut Cursive, verse: &feihua::Verse) {
+    let data = RenderData::get_current_data(s);
+    let content = match data.corpus.borrow().get(&verse.article_id) {
+        Some(art) => art.content.clone(),
+        None => verse.line.clone(),
+    };
+    s.add_layer(
+        Dialog::around(TextView::new(content).scrollable())
+            .title(format!("{} - {}", verse.title, verse.writer))
+            .button("关闭", |s| {
+                s.pop_layer();
+            })
+            .max_width(60),
+    );
+}
+
+fn render_content(s: &mut Cursive, article: &Article) {
+    let data = RenderData::get_current_data(s);
+    s.call_on_name("vertical_text", |view: &mut VerticalText| {
+        view.set_content(data.display(&article.content))
+    })
+    .unwrap();
+    let mode = data.pinyin_mode.get();
+    if mode != PinyinMode::Off {
+        let overrides = data.readings.borrow().get(&article.id);
+        let annotated = reading::annotate(&article.content, &overrides);
+        let text = match mode {
+            PinyinMode::After => reading::render_after(&annotated),
+            _ => reading::render_above(&annotated, 10),
+        };
+        let text = data.display(&text);
+        s.call_on_name("content_text", |view: &mut TextView| view.set_content(text))
+            .unwrap();
+        return;
+    }
+    if data.tone_overlay.get() {
+        if let Some(analysis) = prosody::analyze(&article.poem(), &data.rhymes) {
+            let text = data.display_styled(&render_tones(&article.content, &analysis));
+            s.call_on_name("content_text", |view: &mut TextView| view.set_content(text))
+                .unwrap();
+            return;
+        }
+    }
+    let entries = article.remarks();
+    let markers = data.notes.borrow().markers(article);
+    let mut markers = markers.iter().peekable();
+    let mut text = StyledString::new();
+    let mut last = 0;
+    let spans = remark::locate(&article.content, &entries);
+    for (i, (range, _)) in spans.into_iter().enumerate() {
+        append_marked(
+            &mut text,
+            &article.content[..range.start],
+            last,
+            &mut markers,
+        );
+        let mut style = Style::from(Color::Dark(BaseColor::Cyan)).combine(Effect::Underline);
+        if data.term_cursor.get() == Some(i) {
+            style = style.combine(Effect::Reverse);
+        }
+        text.append_styled(&article.content[range.clone()], style);
+        last = range.end;
+    }
+    append_marked(&mut text, &article.content, last, &mut markers);
+    let text = data.display_styled(&text);
+    s.call_on_name("content_text", |view: &mut TextView| view.set_content(text))
+        .unwrap();
+}
+
+/// 追加 `content[start..]`, 并在其中的句末插入笔记序号
+fn append_marked<'a, I>(
+    text: &mut StyledString,
+    content: &str,
+    mut start: usize,
+    markers: &mut std::iter::Peekable<I>,
+) where
+    I: Iterator<Item = &'a (usize, Vec<usize>)>,
+{
+    while let Some((pos, numbers)) = markers.next_if(|(pos, _)| *pos <= content.len()) {
+        let pos = (*pos).max(start);
+        text.append_plain(&content[start..pos]);
+        let numbers: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+        text.append_styled(
+            format!("[{}]", numbers.join(",")),
+            Color::Dark(BaseColor::Magenta),
+        );
+        start = pos;
+    }
+    text.append_plain(&content[start..]);
+}
+
+/// 刷新笔记层与信息栏的笔记按钮
+fn render_notes(s: &mut Cursive, article: &Article) {
+    let data = RenderData::get_current_data(s);
+    let notes = data.notes.borrow();
+    let own = notes.get(&article.id);
+    let poem = article.poem();
+    let mut text = StyledString::new();
+    if own.is_empty() {
+        text.append_plain("暂无笔记, 可点击 [ 添加 ] 记录");
+    }
+    for (i, note) in own.iter().enumerate() {
+        text.append_styled(
+            format!("[{}] {}\n", i + 1, note.label(&poem)),
+            Color::Dark(BaseColor::Magenta),
+        );
+        text.append_plain(format!("{}\n\n", note.text));
+    }
+    let text = data.display_styled(&text);
+    s.call_on_name("notes_text", |view: &mut TextView| view.set_content(text))
+        .unwrap();
+    let label = if own.is_empty() { "[ × ]" } else { "[ √ ]" };
+    s.call_on_name("notes_btn", |view: &mut Button| {
+        view.enable();
+        view.set_label_raw(label);
+    })
+    .unwrap();
+}
+
+fn render_note_form(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    s.add_layer(
+        Dialog::new()
+            .title(format!("为《{}》添加笔记", data.display(&art.title)))
+            .content(
+                ListView::new()
+                    .child(
+                        "句序",
+                        EditView::new().with_name("note_line").fixed_width(10),
+                    )
+                    .child("", TextView::new("留空为针对全文"))
+                    .child(
+                        "内容",
+                        TextArea::new()
+                            .with_name("note_text")
+                            .min_width(40)
+                            .min_height(5),
+                    ),
+            )
+            .button("保存", move |s| save_note(s, &art))
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+
+fn save_note(s: &mut Cursive, art: &Article) {
+    let data = RenderData::get_current_data(s);
+    let line_raw = s
+        .call_on_name("note_line", |view: &mut EditView| view.get_content())
+        .unwrap();
+    let text = s
+        .call_on_name("note_text", |view: &mut TextArea| {
+            view.get_content().to_string()
+        })
+        .unwrap();
+    let lines = art.poem().lines().count();
+    let line = match line_raw.trim() {
+        "" => None,
+        raw => match raw.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= lines => Some(n - 1),
+            _ => {
+                s.add_layer(Dialog::info(format!("句序应为 1 到 {} 之间的整数", lines)));
+                return;
+            }
+        },
+    };
+    if text.trim().is_empty() {
+        s.add_layer(Dialog::info("笔记内容为空!"));
+        return;
+    }
+    data.notes.borrow_mut().add(&art.id, line, &text);
+    if let Err(e) = data.notes.borrow().save() {
+        s.add_layer(Dialog::info(format!("保存失败: {}", e)));
+        return;
+    }
+    s.pop_layer();
+    render_notes(s, art);
+    render_content(s, art);
+}
+
+fn render_note_menu(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    let notes = data.notes.borrow();
+    if notes.get(&art.id).is_empty() {
+        s.add_layer(Dialog::info("暂无笔记"));
+        return;
+    }
+    let poem = art.poem();
+    let mut select = SelectView::new().autojump();
+    for (i, note) in notes.get(&art.id).iter().enumerate() {
+        let text: String = note.text.chars().take(20).collect();
+        select.add_item(
+            data.display(&format!("[{}] {}：{}", i + 1, note.label(&poem), text)),
+            i,
+        );
+    }
+    select.set_on_submit(move |s, idx: &usize| {
+        let data = RenderData::get_current_data(s);
+        data.notes.borrow_mut().remove(&art.id, *idx);
+        if let Err(e) = data.notes.borrow().save() {
+            s.add_layer(Dialog::info(format!("保存失败: {}", e)));
+            return;
+        }
+        s.pop_layer();
+        render_notes(s, &art);
+        render_content(s, &art);
+    });
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title("删除笔记")
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+
+/// 将正文中的注解词语光标前后移动 `step` 个, 并弹出选中词语的注释
+fn move_term(s: &mut Cursive, step: isize) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    let entries = art.remarks();
+    let spans = remark::locate(&art.content, &entries);
+    if spans.is_empty() {
+        s.add_layer(Dialog::info("正文中没有可注解的词语"));
+        return;
+    }
+    let len = spans.len() as isize;
+    let cursor = match data.term_cursor.get() {
+        Some(cursor) => (cursor as isize + step).rem_euclid(len),
+        None if step > 0 => 0,
+        None => len - 1,
+    } as usize;
+    data.term_cursor.set(Some(cursor));
+    render_content(s, &art);
+    let entry = &entries[spans[cursor].1];
+    s.add_layer(
+        OnEventView::new(
+            Dialog::around(TextView::new(data.display(&entry.explanation)))
+                .title(data.display(&entry.term))
+                .button("关闭", |s| {
+                    s.pop_layer();
+                })
+                .max_width(50),
+        )
+        .on_event(']', |s| {
+            s.pop_layer();
+            move_term(s, 1);
+        })
+        .on_event('[', |s| {
+            s.pop_layer();
+            move_term(s, -1);
+        }),
+    )
+}
+
+fn toggle_parallel_mode(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    data.interleaved.set(!data.interleaved.get());
+    if let Some(art) = data.get_artitle(data.index.get()) {
+        render_parallel(s, &art);
+    }
+}
+
+fn render_parallel(s: &mut Cursive, article: &Article) {
+    let data = RenderData::get_current_data(s);
+    let translation = article.translation.clone().unwrap_or_default();
+    let pairs = align::align(&article.poem(), &translation);
+    s.call_on_name("parallel_rows", |view: &mut LinearLayout| {
+        while view.remove_child(0).is_some() {}
+        let pairs = match pairs {
+            Some(pairs) => pairs,
+            None => {
+                view.add_child(TextView::new("无法逐句对照, 以下为全文对照"));
+                view.add_child(DummyView);
+                vec![align::Pair {
+                    original: article.content.clone(),
+                    translation,
+                }]
+            }
+        };
+        if data.interleaved.get() {
+            let mut text = StyledString::new();
+            for pair in pairs {
+                text.append_styled(data.display(&pair.original), Effect::Bold);
+                text.append_plain(format!("\n{}\n\n", data.display(&pair.translation)));
+            }
+            view.add_child(TextView::new(text));
+        } else {
+            for pair in pairs {
+                view.add_child(
+                    LinearLayout::horizontal()
+                        .child(TextView::new(data.display(&pair.original)).fixed_width(30))
+                        .child(TextView::new(" | "))
+                        .child(TextView::new(data.display(&pair.translation)).full_width()),
+                );
+                view.add_child(DummyView);
+            }
+        }
+    })
+    .unwrap();
+}
+
+fn render_pinyin_menu(s: &mut Cursive) {
+    let mut select = SelectView::new().autojump();
+    select.add_item("关闭拼音", Some(PinyinMode::Off));
+    select.add_item("注于字上", Some(PinyinMode::Above));
+    select.add_item("注于字后", Some(PinyinMode::After));
+    select.add_item("修改读音", None);
+    select.set_on_submit(|s, mode: &Option<PinyinMode>| {
+        s.pop_layer();
+        let data = RenderData::get_current_data(s);
+        match mode {
+            Some(mode) => {
+                data.pinyin_mode.set(*mode);
+                if let Some(art) = data.get_artitle(data.index.get()) {
+                    render_content(s, &art);
+                }
+            }
+            None => render_reading_form(s),
+        }
+    });
+    s.add_layer(Dialog::around(select).title("拼音").button("关闭", |s| {
+        s.pop_layer();
+    }))
+}
+
+fn render_reading_form(s: &mut Cursive) {
+    s.add_layer(
+        Dialog::new()
+            .title("修改读音")
+            .content(
+                ListView::new()
+                    .child(
+                        "字",
+                        EditView::new()
+                            .max_content_width(1)
+                            .with_name("reading_char")
+                            .fixed_width(4),
+                    )
+                    .child(
+                        "读音",
+                        EditView::new().with_name("reading_val").fixed_width(10),
+                    )
+                    .child(
+                        "第几处",
+                        EditView::new().with_name("reading_nth").fixed_width(4),
+                    ),
+            )
+            .button("保存", save_reading)
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn save_reading(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    let get = |s: &mut Cursive, name: &str| {
+        s.call_on_name(name, |view: &mut EditView| view.get_content())
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+    let ch = get(s, "reading_char").chars().next();
+    let val = get(s, "reading_val");
+    let nth = get(s, "reading_nth");
+    let ch = match ch {
+        Some(ch) if !val.is_empty() => ch,
+        _ => {
+            s.add_layer(Dialog::info("请输入字和读音"));
+            return;
+        }
+    };
+    let nth = if nth.is_empty() {
+        None
+    } else {
+        match nth.parse::<usize>() {
+            Ok(n) if n >= 1 => Some(n),
+            _ => {
+                s.add_layer(Dialog::info("请输入正整数( >= 1)"));
+                return;
+            }
+        }
+    };
+    let positions: Vec<usize> = art
+        .content
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| *c == ch)
+        .map(|(i, _)| i)
+        .collect();
+    let positions: Vec<usize> = match nth {
+        Some(n) => positions.get(n - 1).into_iter().cloned().collect(),
+        None => positions,
+    };
+    if positions.is_empty() {
+        s.add_layer(Dialog::info(format!("正文中找不到「{}」", ch)));
+        return;
+    }
+    {
+        let mut readings = data.readings.borrow_mut();
+        for idx in positions {
+            readings.set(&art.id, idx, &val);
+        }
+        if readings.save().is_err() {
+            s.add_layer(Dialog::info("读音保存失败"));
+            return;
+        }
+    }
+    s.pop_layer();
+    if data.pinyin_mode.get() == PinyinMode::Off {
+        data.pinyin_mode.set(PinyinMode::Above);
+    }
+    render_content(s, &art);
+}
+
+fn render_tones(content: &str, analysis: &prosody::Analysis) -> StyledString {
+    let mut marks = vec![];
+    for (i, line) in analysis.lines.iter().enumerate() {
+        for (pos, tone) in line.tones.iter().enumerate() {
+            let wrong = analysis
+                .violations
+                .iter()
+                .any(|v| v.line == i && v.pos == Some(pos));
+            marks.push((*tone, wrong));
+        }
+    }
+    let mut marks = marks.into_iter();
+    let mut text = StyledString::new();
+    for c in content.chars() {
+        if !poem::is_text(c) {
+            text.append_plain(c.to_string());
+            continue;
+        }
+        match marks.next() {
+            Some((tone, wrong)) => {
+                let color = match tone {
+                    Tone::Ping => BaseColor::Blue,
+                    Tone::Ze => BaseColor::Red,
+                    Tone::Either => BaseColor::Yellow,
+                };
+                let mut style = Style::from(Color::Dark(color));
+                if wrong {
+                    style = style.combine(Effect::Reverse);
+                }
+                text.append_styled(c.to_string(), style);
+            }
+            None => text.append_plain(c.to_string()),
+        }
+    }
+    text
+}
+
+fn render_prosody_menu(s: &mut Cursive) {
+    let mut select = SelectView::new().autojump();
+    select.add_item("平仄着色", true);
+    select.add_item("格律报告", false);
+    select.set_on_submit(|s, overlay: &bool| {
+        s.pop_layer();
+        let data = RenderData::get_current_data(s);
+        let art = match data.get_artitle(data.index.get()) {
+            Some(art) => art,
+            None => {
+                s.add_layer(Dialog::info("内容为空!"));
+                return;
+            }
+        };
+        let analysis = match prosody::analyze(&art.poem(), &data.rhymes) {
+            Some(analysis) => analysis,
+            None => {
+                s.add_layer(Dialog::info("仅支持分析近体诗(绝句, 律诗)"));
+                return;
+            }
+        };
+        if *overlay {
+            data.tone_overlay.set(!data.tone_overlay.get());
+            render_content(s, &art);
+        } else {
+            s.add_layer(
+                Dialog::around(TextView::new(prosody::report(&analysis)).scrollable())
+                    .title(format!("格律 - {}", art.title))
+                    .button("关闭", |s| {
+                        s.pop_layer();
+                    }),
+            );
+        }
+    });
+    s.add_layer(Dialog::around(select).title("格律").button("关闭", |s| {
+        s.pop_layer();
+    }))
+}
+
+fn render_script_menu(s: &mut Cursive) {
+    let mut select = SelectView::new().autojump();
+    for script in Script::ALL.iter() {
+        select.add_item(script.name(), *script);
+    }
+    select.set_on_submit(|s, script: &Script| {
+        s.pop_layer();
+        let data = RenderData::get_current_data(s);
+        data.script.set(*script);
+        if script.save().is_err() {
+            s.add_layer(Dialog::info("设置保存失败"));
+        }
+        refresh(s);
+    });
+    s.add_layer(Dialog::around(select).title("简繁").button("关闭", |s| {
+        s.pop_layer();
+    }))
+}
+
+/// 收藏或取消收藏当前文章
+fn toggle_favorite(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let current = data
+        .art_resp
+        .borrow()
+        .as_ref()
+        .and_then(|resp| resp.data.get(data.index.get()).cloned());
+    let art = match current {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("请先选择文章"));
+            return;
+        }
+    };
+    let mut favorites = data.favorites.borrow_mut();
+    let added = favorites.toggle(&art);
+    if let Err(e) = favorites.save() {
+        s.add_layer(Dialog::info(format!("收藏保存失败: {}", e)));
+        return;
+    }
+    let title = data.display(&art.title);
+    s.add_layer(Dialog::info(if added {
+        format!("已收藏《{}》", title)
+    } else {
+        format!("已取消收藏《{}》", title)
+    }));
+}
+
+fn render_collection_menu(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let mut select = SelectView::new();
+    for collection in data.library.borrow().all() {
+        let summary = data.progress.borrow().summary(&collection);
+        select.add_item(
+            format!(
+                "{}  已背 {}/{} ({}%)",
+                data.display(&summary.name),
+                summary.memorized,
+                summary.total,
+                summary.percent()
+            ),
+            collection.name,
+        );
+    }
+    select.set_on_submit(|s, name: &String| {
+        s.pop_layer();
+        open_method(s, Method::Collection(name.clone()));
+    });
+    s.add_layer(
+        Dialog::around(select.with_name("collections").scrollable())
+            .title("选集")
+            .button("标记当前", render_status_menu)
+            .button("导入", render_import_form)
+            .button("导出", export_collection)
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 标记当前文章的背诵状态, 浏览选集时同时刷新完成度
+fn render_status_menu(s: &mut Cursive) {
+    let mut select = SelectView::new().autojump();
+    for status in Status::ALL.iter() {
+        select.add_item(status.name(), *status);
+    }
+    select.set_on_submit(|s, status: &Status| {
+        s.pop_layer();
+        let data = RenderData::get_current_data(s);
+        let current = data
+            .art_resp
+            .borrow()
+            .as_ref()
+            .and_then(|resp| resp.data.get(data.index.get()).cloned())
+            .filter(|art| !art.id.is_empty());
+        let art = match current {
+            Some(art) => art,
+            None => {
+                s.add_layer(Dialog::info("请先选择文章"));
+                return;
+            }
+        };
+        data.progress.borrow_mut().set(&art.id, *status);
+        if let Err(e) = data.progress.borrow().save() {
+            s.add_layer(Dialog::info(format!("进度保存失败: {}", e)));
+            return;
+        }
+        let resp = data.art_resp.borrow().clone();
+        if let Some(resp) = resp.filter(|r| matches!(r.method, Method::Collection(_))) {
+            if let Ok(resp) = data.list(&resp.method, resp.page) {
+                let snippet = resp
+                    .data
+                    .get(data.index.get())
+                    .and_then(|art| art.snippet.clone())
+                    .unwrap_or_default();
+                *data.art_resp.borrow_mut() = Some(resp);
+                s.call_on_name("snippet", |view: &mut TextView| view.set_content(snippet));
+            }
+        }
+        s.pop_layer();
+        s.add_layer(Dialog::info(format!(
+            "《{}》已标记为{}",
+            data.display(&art.title),
+            status.name()
+        )));
+    });
+    s.add_layer(
+        Dialog::around(select)
+            .title("背诵状态")
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn render_import_form(s: &mut Cursive) {
+    s.add_layer(
+        Dialog::new()
+            .title("导入选集")
+            .content(ListView::new().child(
+                "文件",
+                EditView::new().with_name("collection_file").fixed_width(40),
+            ))
+            .button("导入", |s| {
+                let path = s
+                    .call_on_name("collection_file", |view: &mut EditView| view.get_content())
+                    .unwrap();
+                let data = RenderData::get_current_data(s);
+                let result = Collection::read(path.trim()).and_then(|collection| {
+                    let name = collection.name.clone();
+                    let mut library = data.library.borrow_mut();
+                    library.add(collection);
+                    library.save().map(|_| name)
+                });
+                match result {
+                    Ok(name) => {
+                        // 关闭导入框与旧的选集列表, 重新列出
+                        s.pop_layer();
+                        s.pop_layer();
+                        render_collection_menu(s);
+                        s.add_layer(Dialog::info(format!("已导入选集 {}", name)));
+                    }
+                    Err(e) => s.add_layer(Dialog::info(format!("导入失败: {}", e))),
+                }
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn export_collection(s: &mut Cursive) {
+    let name = s
+        .call_on_name("collections", |view: &mut SelectView<String>| {
+            view.selection()
+        })
+        .flatten();
+    let data = RenderData::get_current_data(s);
+    let collection = match name.and_then(|name| data.library.borrow().get(&name)) {
+        Some(collection) => collection,
+        None => {
+            s.add_layer(Dialog::info("请先选择选集"));
+            return;
+        }
+    };
+    let dir = data_dir().join("exports");
+    let path = dir.join(export::file_name(&collection.name, "json"));
+    let result = fs::create_dir_all(&dir)
+        .map_err(Box::<dyn std::error::Error>::from)
+        .and_then(|_| Ok(fs::write(&path, collection.to_json()?)?));
+    match result {
+        Ok(()) => s.add_layer(Dialog::info(format!("已导出到 {}", path.display()))),
+        Err(e) => s.add_layer(Dialog::info(format!("导出失败: {}", e))),
+    }
+}
+
+fn render_tag_menu(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let mut select = SelectView::new();
+    for (tag, count) in data.tags.borrow().names() {
+        select.add_item(format!("{}  {} 篇", data.display(&tag), count), tag);
+    }
+    select.set_on_submit(|s, tag: &String| {
+        s.pop_layer();
+        open_method(s, Method::Tag(tag.clone()));
+    });
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title("标签")
+            .button("标记当前", render_tag_form)
+            .button("移除", render_untag_menu)
+            .button("导入", render_tag_import_form)
+            .button("导出", export_tags)
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 按检索方式打开第一页的第一篇
+fn open_method(s: &mut Cursive, method: Method) {
+    open_page(s, method, 1)
+}
+
+/// 按检索方式打开指定页的第一篇
+fn open_page(s: &mut Cursive, method: Method, page: usize) {
+    let data = RenderData::get_current_data(s);
+    let resp = match data.list(&method, page) {
+        Ok(resp) => resp,
+        Err(e) => {
+            s.add_layer(Dialog::info(format!("内容获取错误: {}", e)));
+            return;
+        }
+    };
+    let first = match resp.data.first() {
+        Some(art) => art.clone(),
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    match data.show(&first) {
+        Ok(article) => {
+            let msg = MSG {
+                article,
+                page: resp.page,
+                total: resp.total,
+                page_size: resp.page_size,
+                index: 0,
+                method: resp.method.clone(),
+            };
+            *data.art_resp.borrow_mut() = Some(resp);
+            data.index.set(0);
+            update(s, msg);
+        }
+        Err(e) => s.add_layer(Dialog::info(format!("内容获取错误: {}", e))),
+    }
+}
+
+/// 当前列表中选中的文章
+fn current_simple(data: &RenderData) -> Option<ArticleSimple> {
+    data.art_resp
+        .borrow()
+        .as_ref()
+        .and_then(|resp| resp.data.get(data.index.get()).cloned())
+        .filter(|art| !art.id.is_empty())
+}
+
+/// 刷新信息栏中文章的标签
+fn render_tags(s: &mut Cursive, id: &str) {
+    let data = RenderData::get_current_data(s);
+    let tags = data.tags.borrow().get(id).join("、");
+    let tags = if tags.is_empty() {
+        "-".to_string()
+    } else {
+        data.display(&tags)
+    };
+    s.call_on_name("tags", |view: &mut TextView| view.set_content(tags))
+        .unwrap();
+}
+
+fn render_tag_form(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match current_simple(&data) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("请先选择文章"));
+            return;
+        }
+    };
+    s.add_layer(
+        Dialog::new()
+            .title(format!("给《{}》加标签", data.display(&art.title)))
+            .content(
+                ListView::new()
+                    .child(
+                        "标签",
+                        EditView::new().with_name("tag_input").fixed_width(20),
+                    )
+                    .child("", TextView::new("多个标签以空格分隔")),
+            )
+            .button("保存", move |s| {
+                let input = s
+                    .call_on_name("tag_input", |view: &mut EditView| view.get_content())
+                    .unwrap();
+                let data = RenderData::get_current_data(s);
+                let input = data.converter.to_simplified(&input);
+                for tag in input.split_whitespace() {
+                    data.tags.borrow_mut().add(&art, tag);
+                }
+                if let Err(e) = data.tags.borrow().save() {
+                    s.add_layer(Dialog::info(format!("保存失败: {}", e)));
+                    return;
+                }
+                // 关闭输入框与旧的标签列表, 重新列出
+                s.pop_layer();
+                s.pop_layer();
+                render_tags(s, &art.id);
+                render_tag_menu(s);
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn render_untag_menu(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match current_simple(&data) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("请先选择文章"));
+            return;
+        }
+    };
+    let tags = data.tags.borrow().get(&art.id).to_vec();
+    if tags.is_empty() {
+        s.add_layer(Dialog::info("当前文章没有标签"));
+        return;
+    }
+    let mut select = SelectView::new().autojump();
+    for tag in tags {
+        select.add_item(data.display(&tag), tag);
+    }
+    select.set_on_submit(move |s, tag: &String| {
+        let data = RenderData::get_current_data(s);
+        data.tags.borrow_mut().remove(&art.id, tag);
+        if let Err(e) = data.tags.borrow().save() {
+            s.add_layer(Dialog::info(format!("保存失败: {}", e)));
+            return;
+        }
+        s.pop_layer();
+        s.pop_layer();
+        render_tags(s, &art.id);
+        render_tag_menu(s);
+    });
+    s.add_layer(
+        Dialog::around(select)
+            .title("移除标签")
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn render_tag_import_form(s: &mut Cursive) {
+    s.add_layer(
+        Dialog::new()
+            .title("导入标签")
+            .content(ListView::new().child(
+                "文件",
+                EditView::new().with_name("tag_file").fixed_width(40),
+            ))
+            .button("导入", |s| {
+                let path = s
+                    .call_on_name("tag_file", |view: &mut EditView| view.get_content())
+                    .unwrap();
+                let data = RenderData::get_current_data(s);
+                let result = Tags::read(path.trim()).and_then(|imported| {
+                    let mut tags = data.tags.borrow_mut();
+                    let added = tags.merge(imported);
+                    tags.save().map(|_| added)
+                });
+                match result {
+                    Ok(added) => {
+                        s.pop_layer();
+                        s.pop_layer();
+                        render_tag_menu(s);
+                        s.add_layer(Dialog::info(format!("已导入 {} 个标签", added)));
+                    }
+                    Err(e) => s.add_layer(Dialog::info(format!("导入失败: {}", e))),
+                }
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn export_tags(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let dir = data_dir().join("exports");
+    let path = dir.join("tags.json");
+    let result = fs::create_dir_all(&dir)
+        .map_err(Box::<dyn std::error::Error>::from)
+        .and_then(|_| Ok(fs::write(&path, data.tags.borrow().to_json()?)?));
+    match result {
+        Ok(()) => s.add_layer(Dialog::info(format!("已导出到 {}", path.display()))),
+        Err(e) => s.add_layer(Dialog::info(format!("导出失败: {}", e))),
+    }
+}
+
+fn render_concordance_form(s: &mut Cursive) {
+    let mut order_group: RadioGroup<concordance::Order> = RadioGroup::new();
+    let mut orders = LinearLayout::horizontal();
+    for order in concordance::Order::ALL.iter() {
+        orders.add_child(order_group.button(*order, order.name()));
+    }
+    s.add_layer(
+        Dialog::new()
+            .title("意象索引")
+            .content(
+                ListView::new()
+                    .child(
+                        "字词",
+                        EditView::new()
+                            .with_name("concordance_keyword")
+                            .fixed_width(10),
+                    )
+                    .child("排序", orders),
+            )
+            .button("检索", move |s| {
+                let keyword = s
+                    .call_on_name("concordance_keyword", |view: &mut EditView| {
+                        view.get_content()
+                    })
+                    .unwrap();
+                show_concordance(s, &keyword, *order_group.selection());
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 逐句列出本地文集中含有该字词的诗句, 选中后打开文章并定位到该句
+fn show_concordance(s: &mut Cursive, keyword: &str, order: concordance::Order) {
+    let data = RenderData::get_current_data(s);
+    let keyword = data.converter.to_simplified(keyword);
+    let mut hits = concordance::search(&data.corpus.borrow(), &keyword);
+    if hits.is_empty() {
+        s.add_layer(Dialog::info("本地文集中没有找到"));
+        return;
+    }
+    concordance::sort(&mut hits, order);
+    let title = format!("「{}」共 {} 处", data.display(&hits[0].keyword), hits.len());
+    let mut select = SelectView::new();
+    for hit in hits {
+        let label = format!("{}  {}", hit.kwic(8), hit.source());
+        select.add_item(data.display(&label), hit);
+    }
+    select.set_on_submit(open_hit);
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title(title)
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 以诗句检索打开出处, 便于前后翻看同一意象的其他作品
+fn open_hit(s: &mut Cursive, hit: &concordance::Hit) {
+    let data = RenderData::get_current_data(s);
+    let method = Method::Line(format!("*{}*", hit.keyword));
+    let found = match LinePattern::parse(&format!("*{}*", hit.keyword)) {
+        Ok(pattern) => search::search_lines(&data.corpus.borrow(), &pattern),
+        Err(e) => {
+            s.add_layer(Dialog::info(format!("检索错误: {}", e)));
+            return;
+        }
+    };
+    let pos = match found.iter().position(|art| art.id == hit.id) {
+        Some(pos) => pos,
+        None => {
+            s.add_layer(Dialog::info(format!(
+                "检索结果中未找到《{}》",
+                data.display(&hit.title)
+            )));
+            return;
+        }
+    };
+    let resp = ArtListResp::paginate(found, pos / LOCAL_PAGE_SIZE + 1, method);
+    let index = pos % LOCAL_PAGE_SIZE;
+    let article = match resp.data.get(index).map(|art| data.show(art)) {
+        Some(Ok(article)) => article,
+        _ => {
+            s.add_layer(Dialog::info("内容获取错误"));
+            return;
+        }
+    };
+    let msg = MSG {
+        article: article.clone(),
+        page: resp.page,
+        total: resp.total,
+        page_size: resp.page_size,
+        index,
+        method: resp.method.clone(),
+    };
+    *data.art_resp.borrow_mut() = Some(resp);
+    data.index.set(index);
+    // 关闭索引列表与检索框
+    s.pop_layer();
+    s.pop_layer();
+    update(s, msg);
+    focus_line(s, &article, hit.line);
+}
+
+/// 在正文中高亮一句并滚动到该处
+fn focus_line(s: &mut Cursive, article: &Article, line: usize) {
+    let data = RenderData::get_current_data(s);
+    let span = match poem::line_spans(&article.content).get(line) {
+        Some(span) => span.clone(),
+        None => return,
+    };
+    let mut text = StyledString::plain(&article.content[..span.start]);
+    text.append_styled(&article.content[span.clone()], Effect::Reverse);
+    text.append_plain(&article.content[span.end..]);
+    let text = data.display_styled(&text);
+    s.call_on_name("content_text", |view: &mut TextView| view.set_content(text))
+        .unwrap();
+    let row = article.content[..span.start].matches('\n').count();
+    s.call_on_name(
+        "content_scroll",
+        |view: &mut ScrollView<NamedView<TextView>>| view.set_offset((0, row)),
+    );
+    if data.sview_vec.borrow().last().map(String::as_str) != Some("c") {
+        visible_view(s, "c");
+    }
+}
+
+/// 列出当前文章的相关作品, 选中后在阅读区打开
+fn render_related(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    let resp = match data.list(&Method::Related(art.id.clone()), 1) {
+        Ok(resp) if !resp.data.is_empty() => resp,
+        _ => {
+            s.add_layer(Dialog::info("本地文集中没有相关作品"));
+            return;
+        }
+    };
+    let mut select = SelectView::new();
+    for (i, item) in resp.data.iter().enumerate() {
+        let label = format!(
+            "{}  {}",
+            item.title,
+            item.snippet.as_deref().unwrap_or_default()
+        );
+        select.add_item(data.display(&label), i);
+    }
+    select.set_on_submit(move |s, index: &usize| {
+        let data = RenderData::get_current_data(s);
+        let article = match data.show(&resp.data[*index]) {
+            Ok(article) => article,
+            Err(e) => {
+                s.add_layer(Dialog::info(format!("内容获取错误: {}", e)));
+                return;
+            }
+        };
+        let msg = MSG {
+            article,
+            page: resp.page,
+            total: resp.total,
+            page_size: resp.page_size,
+            index: *index,
+            method: resp.method.clone(),
+        };
+        *data.art_resp.borrow_mut() = Some(resp.clone());
+        data.index.set(*index);
+        s.pop_layer();
+        update(s, msg);
+    });
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title(format!("《{}》的相关作品", data.display(&art.title)))
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+fn render_theme_menu(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let mut select = SelectView::new();
+    for (theme, count) in data.lexicon.counts(&data.corpus.borrow()) {
+        select.add_item(format!("{}  {} 篇", theme, count), theme);
+    }
+    select.set_on_submit(|s, theme: &String| {
+        s.pop_layer();
+        open_method(s, Method::Theme(theme.clone()));
+    });
+    s.add_layer(
+        Dialog::around(select.scrollable())
+            .title("题材")
+            .button("本篇依据", show_theme_evidence)
+            .button("同类作品", |s| {
+                let data = RenderData::get_current_data(s);
+                let themes = data
+                    .get_artitle(data.index.get())
+                    .map(|art| data.lexicon.classify(&art))
+                    .unwrap_or_default();
+                if themes.is_empty() {
+                    s.add_layer(Dialog::info("当前文章未归入任何题材"));
+                    return;
+                }
+                let query: Vec<String> = themes.into_iter().map(|l| l.theme).collect();
+                s.pop_layer();
+                open_method(s, Method::Theme(query.join("、")));
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 列出当前文章的题材及命中的线索词
+fn show_theme_evidence(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+    };
+    let labels = data.lexicon.classify(&art);
+    let text = if labels.is_empty() {
+        "未归入任何题材".to_string()
+    } else {
+        labels
+            .iter()
+            .map(|l| {
+                format!(
+                    "{} (得分 {})\n  {}",
+                    l.theme,
+                    l.score,
+                    l.evidence.join("、")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    s.add_layer(
+        Dialog::around(TextView::new(data.display(&text)).scrollable())
+            .title(format!("《{}》的题材", data.display(&art.title)))
+            .button("关闭", |s| {
+                s.pop_layer();
+            })
+            .max_width(60),
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportScope {
+    Current,
+    Favorites,
+    Results,
+}
+
+/// 导出为文档, 或导出为 Anki 卡片
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportKind {
+    Document(export::Format),
+    Anki,
+}
+
+fn render_export_form(s: &mut Cursive) {
+    let mut scope_group: RadioGroup<ExportScope> = RadioGroup::new();
+    let mut format_group: RadioGroup<ExportKind> = RadioGroup::new();
+    let mut formats = LinearLayout::horizontal();
+    for format in export::Format::ALL.iter() {
+        formats.add_child(format_group.button(ExportKind::Document(*format), format.name()));
+    }
+    formats.add_child(format_group.button(ExportKind::Anki, "Anki"));
+    let mut templates = LinearLayout::horizontal();
+    for (i, template) in anki::Template::ALL.iter().enumerate() {
+        templates.add_child(
+            Checkbox::new()
+                .checked()
+                .with_name(format!("anki_template_{}", i)),
+        );
+        templates.add_child(TextView::new(format!(" {}  ", template.name())));
+    }
+    let dir = data_dir().join("exports");
+    s.add_layer(
+        Dialog::new()
+            .title("导出")
+            .content(
+                ListView::new()
+                    .child(
+                        "范围",
+                        LinearLayout::horizontal()
+                            .child(scope_group.button(ExportScope::Current, "当前文章"))
+                            .child(scope_group.button(ExportScope::Favorites, "收藏"))
+                            .child(scope_group.button(ExportScope::Results, "检索结果")),
+                    )
+                    .child("格式", formats)
+                    .child("卡片", templates)
+                    .child(
+                        "笔记",
+                        LinearLayout::horizontal()
+                            .child(Checkbox::new().with_name("export_notes"))
+                            .child(TextView::new(" 附带个人笔记")),
+                    )
+                    .child(
+                        "目录",
+                        EditView::new()
+                            .content(dir.to_string_lossy())
+                            .with_name("export_dir")
+                            .fixed_width(40),
+                    ),
+            )
+            .button("导出", move |s| {
+                export_articles(s, *scope_group.selection(), *format_group.selection())
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 导出范围内的文章, 导出标题, 以及选集中未能找到而略过的标题
+struct Collected {
+    articles: Vec<Article>,
+    title: String,
+    missing: Vec<String>,
+}
+
+/// 收集导出范围内的文章及导出标题
+fn collect_export(
+    data: &RenderData,
+    scope: ExportScope,
+) -> Result<Collected, Box<dyn std::error::Error>> {
+    let resp = data.art_resp.borrow().clone();
+    match scope {
+        ExportScope::Current => {
+            let art = data
+                .get_artitle(data.index.get())
+                .ok_or(APIError::NotFound)?;
+            let title = art.title.clone();
+            Ok(Collected {
+                articles: vec![art],
+                title,
+                missing: vec![],
+            })
+        }
+        ExportScope::Favorites => {
+            let items = data.favorites.borrow().items().to_vec();
+            let articles = items
+                .iter()
+                .map(|art| data.show(art))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Collected {
+                articles,
+                title: "收藏".to_string(),
+                missing: vec![],
+            })
+        }
+        ExportScope::Results => {
+            let resp = resp.ok_or(APIError::NotFound)?;
+            let items = export::collect_results(resp.page, |page| {
+                if page == resp.page {
+                    Ok(resp.clone())
+                } else {
+                    data.list(&resp.method, page)
+                }
+            })?;
+            let (found, missing): (Vec<&ArticleSimple>, Vec<&ArticleSimple>) =
+                items.iter().partition(|art| !art.id.is_empty());
+            let articles = found
+                .into_iter()
+                .map(|art| data.show(art))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Collected {
+                articles,
+                title: format!("检索结果 {}", resp.method.label()),
+                missing: missing.iter().map(|art| art.title.clone()).collect(),
+            })
+        }
+    }
+}
+
+fn export_articles(s: &mut Cursive, scope: ExportScope, kind: ExportKind) {
+    let data = RenderData::get_current_data(s);
+    let dir = s
+        .call_on_name("export_dir", |view: &mut EditView| view.get_content())
+        .unwrap();
+    let with_notes = s
+        .call_on_name("export_notes", |view: &mut Checkbox| view.is_checked())
+        .unwrap();
+    let Collected {
+        articles,
+        title,
+        missing,
+    } = match collect_export(&data, scope) {
+        Ok(found) if found.articles.is_empty() => {
+            s.add_layer(Dialog::info("内容为空!"));
+            return;
+        }
+        Ok(found) => found,
+        Err(e) => {
+            s.add_layer(Dialog::info(format!("内容获取错误: {}", e)));
+            return;
+        }
+    };
+    let articles: Vec<Article> = articles
+        .iter()
+        .map(|art| data.display_article(art))
+        .collect();
+    let title = data.display(&title);
+    let result = match kind {
+        ExportKind::Document(format) => {
+            let path = Path::new(dir.as_str()).join(export::file_name(&title, format.extension()));
+            let notes = with_notes.then(|| data.notes.borrow());
+            export::export(
+                &path,
+                &articles,
+                &title,
+                format,
+                &data.readings.borrow(),
+                notes.as_deref(),
+            )
+            .map(|_| path)
+        }
+        ExportKind::Anki => export_anki(s, &data, &articles, &title, Path::new(dir.as_str())),
+    };
+    match result {
+        Ok(path) => {
+            s.pop_layer();
+            let mut info = format!("已导出到 {}", path.display());
+            if !missing.is_empty() {
+                info.push_str(&format!("\n未找到, 已略过: {}", missing.join("、")));
+            }
+            s.add_layer(Dialog::info(info));
+        }
+        Err(e) => s.add_layer(Dialog::info(format!("导出失败: {}", e))),
+    }
+}
+
+/// 按勾选的模板生成卡片, 名句取缓存中出处为所选文章的那些
+fn export_anki(
+    s: &mut Cursive,
+    data: &RenderData,
+    articles: &[Article],
+    title: &str,
+    dir: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let templates: Vec<anki::Template> = anki::Template::ALL
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            s.call_on_name(&format!("anki_template_{}", i), |view: &mut Checkbox| {
+                view.is_checked()
+            })
+            .unwrap_or(false)
+        })
+        .map(|(_, template)| *template)
+        .collect();
+    let cached = fortune::load_sentences();
+    let sentences: Vec<Sentence> = anki::related_sentences(articles, &cached)
+        .into_iter()
+        .map(|sentence| Sentence {
+            name: data.display(&sentence.name),
+            from: data.display(&sentence.from),
+            ..sentence.clone()
+        })
+        .collect();
+    let cards = anki::cards(articles, &sentences, &templates);
+    if cards.is_empty() {
+        return Err(Box::new(APIError::NotFound));
+    }
+    let path = dir.join(export::file_name(title, anki::EXTENSION));
+    fs::create_dir_all(dir)?;
+    fs::write(&path, anki::to_tsv(&cards, title))?;
+    Ok(path)
+}
+
+fn render_practice_form(s: &mut Cursive) {
+    let mut grid_group: RadioGroup<practice::Grid> = RadioGroup::new();
+    let mut grids = LinearLayout::horizontal();
+    for grid in practice::Grid::ALL.iter() {
+        grids.add_child(grid_group.button(*grid, grid.name()));
+    }
+    let dir = data_dir().join("practice");
+    s.add_layer(
+        Dialog::new()
+            .title("字帖")
+            .content(
+                ListView::new()
+                    .child("格子", grids)
+                    .child(
+                        "描红",
+                        Checkbox::new().checked().with_name("practice_trace"),
+                    )
+                    .child(
+                        "拼音",
+                        Checkbox::new().checked().with_name("practice_pinyin"),
+                    )
+                    .child(
+                        "练习次数",
+                        EditView::new()
+                            .content((practice::COLUMNS - 1).to_string())
+                            .with_name("practice_repeat")
+                            .fixed_width(4),
+                    )
+                    .child(
+                        "目录",
+                        EditView::new()
+                            .content(dir.to_string_lossy())
+                            .with_name("practice_dir")
+                            .fixed_width(40),
+                    ),
+            )
+            .button("生成", move |s| {
+                practice_sheet(s, *grid_group.selection())
+            })
+            .button("关闭", |s| {
+                s.pop_layer();
+            }),
+    )
+}
+
+/// 按当前文章的正文与读音生成字帖
+fn practice_sheet(s: &mut Cursive, grid: practice::Grid) {
+    let data = RenderData::get_current_data(s);
+    let art = match data.get_artitle(data.index.get()) {
+        Some(art) => art,
+        None => {
+            s.add_layer(Dialog::info("没有可用的文章"));
+            return;
+        }
+    };
+    let checked = |s: &mut Cursive, name: &str| {
+        s.call_on_name(name, |view: &mut Checkbox| view.is_checked())
+            .unwrap_or(false)
+    };
+    let repeat = s
+        .call_on_name("practice_repeat", |view: &mut EditView| view.get_content())
+        .and_then(|content| content.trim().parse::<usize>().ok());
+    let repeat = match repeat {
+        Some(repeat) => repeat,
+        None => {
+            s.add_layer(Dialog::info("练习次数须为数字"));
+            return;
+        }
+    };
+    let opts = practice::Options {
+        grid,
+        trace: checked(s, "practice_trace"),
+        pinyin: checked(s, "practice_pinyin"),
+        repeat,
+    };
+    let dir = s
+        .call_on_name("practice_dir", |view: &mut EditView| view.get_content())
+        .unwrap();
+    let overrides = data.readings.borrow().get(&art.id);
+    let mut annotated = reading::annotate(&art.content, &overrides);
+    // 读音按原文标注, 字形按显示设置转换, 转换改变字数时保留原文
+    let shown: Vec<char> = data.display(&art.content).chars().collect();
+    if shown.len() == annotated.len() {
+        for (a, ch) in annotated.iter_mut().zip(shown) {
+            a.ch = ch;
+        }
+    }
+    let title = data.display(&art.title);
+    let pages = practice::render(&title, &annotated, &opts);
+    match practice::save(Path::new(dir.as_str()), &title, &pages) {
+        Ok(paths) if paths.is_empty() => s.add_layer(Dialog::info("正文中没有可练习的字")),
+        Ok(paths) => {
+            s.pop_layer();
+            s.add_layer(Dialog::info(format!(
+                "已生成 {} 页到 {}",
+                paths.len(),
+                dir.as_str()
+            )));
+        }
+        Err(e) => s.add_layer(Dialog::info(format!("生成失败: {}", e))),
+    }
+}
+
+/// 按当前设置重新显示当前文章
+fn refresh(s: &mut Cursive) {
+    let data = RenderData::get_current_data(s);
+    let idx = data.index.get();
+    let resp = data.art_resp.borrow().clone();
+    if let (Some(resp), Some(article)) = (resp, data.get_artitle(idx)) {
+        let msg = MSG {
+            article,
+            page: resp.page,
+            total: resp.total,
+            page_size: resp.page_size,
+            index: idx,
+            method: resp.method,
+        };
+        update(s, msg);
+    }
+}
@@ -0,0 +1,173 @@
+use cursive::event::{Event, EventResult, Key, MouseEvent};
+use cursive::{direction::Direction, Printer, Vec2, View};
+
+/// 每列占用的宽度: 全角字两列, 列间空一列
+const COLUMN_STRIDE: usize = 3;
+
+/// 竖排视图: 自上而下成列, 自右向左排列, 左右键或滚轮横向翻看
+#[derive(Default)]
+pub struct VerticalText {
+    content: String,
+    columns: Vec<Vec<char>>,
+    height: usize,
+    /// 右侧已翻过的列数
+    offset: usize,
+    visible: usize,
+}
+
+impl VerticalText {
+    pub fn new() -> VerticalText {
+        VerticalText::default()
+    }
+
+    pub fn set_content<S: Into<String>>(&mut self, content: S) {
+        self.content = content.into();
+        self.offset = 0;
+        self.columns = layout_columns(&self.content, self.height);
+    }
+
+    fn scroll(&mut self, delta: isize) -> EventResult {
+        let max = self.columns.len().saturating_sub(self.visible);
+        let offset = (self.offset as isize + delta).max(0) as usize;
+        self.offset = offset.min(max);
+        EventResult::Consumed(None)
+    }
+}
+
+impl View for VerticalText {
+    fn draw(&self, printer: &Printer) {
+        let width = printer.size.x;
+        for (i, column) in self
+            .columns
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(self.visible)
+        {
+            let x = width - (i - self.offset + 1) * COLUMN_STRIDE;
+            for (y, c) in column.iter().enumerate() {
+                printer.print((x, y), &c.to_string());
+            }
+        }
+        let bottom = printer.size.y.saturating_sub(1);
+        if self.offset + self.visible < self.columns.len() {
+            printer.print((0, bottom), "«");
+        }
+        if self.offset > 0 {
+            printer.print((width.saturating_sub(1), bottom), "»");
+        }
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        // 最后一行留给翻页提示
+        let height = size.y.saturating_sub(1).max(1);
+        if height != self.height {
+            self.height = height;
+            self.columns = layout_columns(&self.content, height);
+        }
+        self.visible = size.x / COLUMN_STRIDE;
+        self.offset = self
+            .offset
+            .min(self.columns.len().saturating_sub(self.visible));
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        constraint
+    }
+
+    fn take_focus(&mut self, _: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        let page = self.visible.max(1) as isize;
+        match event {
+            Event::Key(Key::Left)
+            | Event::Mouse {
+                event: MouseEvent::WheelDown,
+                ..
+            } => self.scroll(1),
+            Event::Key(Key::Right)
+            | Event::Mouse {
+                event: MouseEvent::WheelUp,
+                ..
+            } => self.scroll(-1),
+            Event::Key(Key::PageDown) => self.scroll(page),
+            Event::Key(Key::PageUp) => self.scroll(-page),
+            Event::Key(Key::Home) => self.scroll(-(self.offset as isize)),
+            Event::Key(Key::End) => self.scroll(self.columns.len() as isize),
+            _ => EventResult::Ignored,
+        }
+    }
+}
+
+/// 将文本按列高切分, 换行另起一列, 标点转为竖排形式, 半角字符转为全角
+pub fn layout_columns(content: &str, height: usize) -> Vec<Vec<char>> {
+    let height = height.max(1);
+    let mut columns = vec![];
+    for paragraph in content.lines() {
+        let chars: Vec<char> = paragraph
+            .trim()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(vertical_form)
+            .collect();
+        for chunk in chars.chunks(height) {
+            columns.push(chunk.to_vec());
+        }
+    }
+    columns
+}
+
+fn vertical_form(c: char) -> char {
+    match c {
+        '，' | ',' => '︐',
+        '、' => '︑',
+        '。' | '.' => '︒',
+        '：' | ':' => '︓',
+        '；' | ';' => '︔',
+        '！' | '!' => '︕',
+        '？' | '?' => '︖',
+        '「' | '“' => '﹁',
+        '」' | '”' => '﹂',
+        '『' | '‘' => '﹃',
+        '』' | '’' => '﹄',
+        '（' | '(' => '︵',
+        '）' | ')' => '︶',
+        '《' => '︽',
+        '》' => '︾',
+        '〈' => '︿',
+        '〉' => '﹀',
+        '【' => '︻',
+        '】' => '︼',
+        '—' => '︱',
+        '…' => '︙',
+        // 背诵遮挡
+        '_' => '□',
+        '!'..='~' => std::char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::JINGYESI;
+
+    #[test]
+    fn test_layout_columns() {
+        let columns = layout_columns(JINGYESI, 8);
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[0].iter().collect::<String>(), "床前明月光︐疑是");
+        assert_eq!(columns[1].iter().collect::<String>(), "地上霜︒");
+        assert_eq!(columns[2][0], '举');
+    }
+
+    #[test]
+    fn test_vertical_form() {
+        assert_eq!(vertical_form('《'), '︽');
+        assert_eq!(vertical_form('_'), '□');
+        assert_eq!(vertical_form('A'), 'Ａ');
+        assert_eq!(vertical_form('月'), '月');
+    }
+}